@@ -0,0 +1,93 @@
+//! 战斗结果模拟
+//!
+//! 消息结构及模拟规则来自 battle_proto,与探索服和客户端一致
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+pub use battle_proto::{BattleCharacterResult, BattleOutcome, BattleRequest, BattleResult, BattleUnit, CharacterState};
+///指定敌人的固定结果
+///
+/// 固定结果与客户端模拟不一致,探索服会拒绝客户端上报,多次不一致后以战斗服结果为准,仅用于调试指定结果
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct MockRule{
+    pub enemy_id: u32,
+    pub win: bool,
+    ///胜利时每个角色损失的最大生命百分比
+    #[serde(default)]
+    pub damage: i32,
+    #[serde(default = "default_round")]
+    pub round: u32,
+}
+fn default_round() -> u32 { 1 }
+///模拟配置
+#[derive(Debug, Clone, Default)]
+pub struct MockConfig{
+    pub rules: Vec<MockRule>,
+}
+static MOCK_CONFIG: Lazy<RwLock<MockConfig>> = Lazy::new(|| RwLock::new(MockConfig::default()));
+///加载模拟配置
+pub fn load_rules() -> anyhow::Result<()>{
+    let rules = match shared::libconfig::config::get_str("rules_file") {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).map_err(|e| logthrow!(e, e))?;
+            let rules = serde_json::from_str::<Vec<MockRule>>(&content)?;
+            info!("load {} battle rules from {}", rules.len(), path);
+            rules
+        },
+        None => Vec::new(),
+    };
+    *MOCK_CONFIG.write().unwrap() = MockConfig{ rules };
+    Ok(())
+}
+///计算战斗结果
+///
+/// 没有匹配规则时按请求种子模拟,与探索服和客户端结果一致
+pub fn decide(request: &BattleRequest, config: &MockConfig) -> BattleOutcome{
+    let rule = match config.rules.iter().find(|r| r.enemy_id == request.enemy.id) {
+        Some(rule) => rule,
+        None => return request.simulate(),
+    };
+    BattleOutcome{
+        win: rule.win,
+        round: rule.round,
+        characters: request.party.iter().map(|unit| {
+            let health = if rule.win { (unit.health - unit.max_health * rule.damage / 100).max(1) } else { 1 };
+            BattleCharacterResult{
+                id: unit.id,
+                health,
+                state: if health > 1 { CharacterState::Active } else { CharacterState::Injured },
+            }
+        }).collect(),
+    }
+}
+///处理战斗请求
+pub fn handle_request(request: BattleRequest) -> BattleResult{
+    let outcome = decide(&request, &MOCK_CONFIG.read().unwrap());
+    info!("player {} battle {} with enemy {}, win {}", request.player_id, request.event_id, request.enemy.id, outcome.win);
+    BattleResult{
+        player_id: request.player_id,
+        event_id: request.event_id,
+        outcome,
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_decide(){
+    let request = BattleRequest{
+        player_id: 1,
+        event_id: 100,
+        seed: 0x5eed,
+        party: vec![BattleUnit{ id: 10111, attack: 120, defense: 80, health: 1000, max_health: 1000 }],
+        enemy: battle_proto::BattleEnemy{ id: 7, attack: 150, defense: 60, health: 1500 },
+    };
+    let mut config = MockConfig::default();
+    assert_eq!(decide(&request, &config), battle_proto::simulate(&request.party, &request.enemy, request.seed));
+    config.rules.push(MockRule{ enemy_id: 7, win: true, damage: 20, round: 3 });
+    let outcome = decide(&request, &config);
+    assert!(outcome.win);
+    assert_eq!(outcome.round, 3);
+    assert_eq!(outcome.characters[0].health, 800);
+    config.rules[0].win = false;
+    let outcome = decide(&request, &config);
+    assert!(!outcome.win);
+    assert_eq!(outcome.characters[0].state, CharacterState::Injured);
+}
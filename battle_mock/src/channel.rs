@@ -0,0 +1,143 @@
+//! 战斗服务通讯channel
+use protobuf::well_known_types::StringValue;
+use shared::{AsyncSessionHandler, AsyncSocketHandler, AsyncSocketSendHandler, SessionTransport, TransportReceiver, Transporter, proto::PackBuffer, server::channel::{AsyncServiceChannel, AsyncServiceDataHandler, ChannelState}, timer::IntervalTimer};
+///启动服务channel
+pub fn start_up() -> anyhow::Result<()>{
+    shared::server::channel::async_channel::start::<()>(
+        &shared::libconfig::config::get_str("bind_ip").expect("fail"),
+        shared::libconfig::config::get("channel_port").expect("config channel_port expected")
+    )?;
+    let (tx,rx) = tokio::sync::mpsc::unbounded_channel();
+    shared::server::channel::async_channel::set_async_channel_handler(Box::new(move |session: Box<dyn (::std::any::Any) + Send + Sync + 'static>|{
+        if let Ok(result) = session.downcast::<AsyncSessionHandler<()>>(){
+            tx.send(*result).ok();
+        }
+    }));
+    let channel = shared::server::this_channel::AsyncThisChannel::<BattleSession,SessionTransport<()>, BattleDataSession, ()>::new(rx, 2);
+    channel.run()?;
+    Ok(())
+}
+///探索服会话
+pub struct BattleSession{
+    handler: AsyncSessionHandler<()>,
+    state: ChannelState,
+    heart_timer: IntervalTimer,
+    channele_type: shared::proto::ChannelClientType,
+    ///保持channel_recv的发送端存活
+    #[allow(unused)]
+    channel_handler: AsyncSocketSendHandler<()>,
+    channel_recv: Option<AsyncSocketHandler<()>>,
+    transporter: Option<Transporter<()>>,
+    transport_handler: Option<TransportReceiver<()>>
+}
+impl BattleSession{
+    ///处理战斗请求并返回结果
+    fn on_battle_req(&mut self, packet: PackBuffer) -> anyhow::Result<()>{
+        let pack = packet.unpack::<StringValue>().map_err(|_| shared::error::unpack_err())?;
+        let request = serde_json::from_str::<super::battle::BattleRequest>(pack.get_value()).map_err(|e| logthrow!(e, e))?;
+        let result = super::battle::handle_request(request);
+        let mut msg = StringValue::new();
+        msg.set_value(serde_json::to_string(&result)?);
+        self.handler.send(SessionTransport::new(
+            shared::proto::proto_code::DEFAULT_MAIN_CODE,
+            super::msg_id::BATTLE_RESULT_SYNC,
+            packet.header().squence(),
+            Box::new(msg)))?;
+        Ok(())
+    }
+}
+pub struct BattleDataSession{
+    transport_handler: TransportReceiver<()>
+}
+#[async_trait]
+impl AsyncServiceDataHandler<SessionTransport<()>> for BattleDataSession{
+    async fn deal(&mut self)-> anyhow::Result<Option<SessionTransport<()>>> {
+        Ok(self.transport_handler.recv().await)
+    }
+}
+#[async_trait]
+impl AsyncServiceChannel<SessionTransport<()>, BattleDataSession, ()> for BattleSession{
+    #[inline]
+    fn handler_mut(&mut self) -> &mut AsyncSessionHandler<()> {
+        &mut self.handler
+    }
+    #[inline]
+    fn handler(&self) -> &AsyncSessionHandler<()> {
+        &self.handler
+    }
+    #[inline]
+    fn state(&self) -> ChannelState {
+        self.state
+    }
+
+    fn new(handler: AsyncSessionHandler<()>, state: ChannelState) -> Self {
+        let (tx,rx) = tokio::sync::mpsc::unbounded_channel();
+        let (sh,rh) = tokio::sync::mpsc::unbounded_channel();
+        Self{
+            handler,
+            heart_timer: IntervalTimer::new(30*1000),
+            state,
+            channele_type: shared::proto::ChannelClientType::UnDefined,
+            channel_handler: tx.into(),
+            channel_recv: rx.into(),
+            transporter: sh.into(),
+            transport_handler: rh.into(),
+        }
+    }
+
+    async fn on_packet(&mut self, packet: PackBuffer)-> anyhow::Result<()> {
+        match packet.header().sub_code() as u16{
+            super::msg_id::BATTLE_START_REQ => self.on_battle_req(packet)?,
+            opcode => {
+                warn!("unexpected opcode {} from channel {:?}",opcode,self.client_type());
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn heart_timer(&mut self) -> &mut IntervalTimer {
+        &mut self.heart_timer
+    }
+
+    #[inline]
+    fn set_state(&mut self, s: ChannelState) {
+        self.state = s;
+    }
+    fn client_type(&self) -> shared::proto::ChannelClientType { self.channele_type.clone() }
+    #[inline]
+    fn set_client_type(&mut self, ct: shared::proto::ChannelClientType) {
+        self.channele_type = ct;
+    }
+
+    async fn deal_msg(&mut self, msg: SessionTransport<()>) -> anyhow::Result<()> {
+        self.handler.send(msg)?;
+        Ok(())
+    }
+
+    fn channel_handler(&mut self) -> Transporter<()> {
+        self.transporter.take().expect("channel_handler taken")
+    }
+
+    fn splite_channel_handler(&mut self)-> AsyncSocketHandler<()> {
+        self.channel_recv.take().expect("splite_channel_handler fail, can not splite twice")
+    }
+
+    async fn deal_channel_msg(&mut self, msg: shared::SocketMessage<()>) -> anyhow::Result<()> {
+        warn!("deal_channel_msg {:?} ignored",msg);
+        Ok(())
+    }
+
+    async fn deal_teamplate_msg(&mut self, _msg: ()) -> anyhow::Result<()> {
+        Ok(())
+    }
+    fn split_data_handler(&mut self) -> Option<BattleDataSession> {
+        BattleDataSession{
+            transport_handler: self.transport_handler.take().expect("split_data_handler fail")
+        }.into()
+    }
+
+    fn on_close(&mut self) {
+        info!("explore server channel {} closed", self.session_id());
+    }
+}
@@ -0,0 +1,9 @@
+//! 战斗服模拟
+//!
+//! 战斗计算部分作为库提供给探索服的联调测试
+#[allow(unused_imports)]
+#[macro_use]
+extern crate shared;
+#[macro_use]
+extern crate log;
+pub mod battle;
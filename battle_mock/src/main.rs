@@ -0,0 +1,27 @@
+//! 战斗服模拟
+//!
+//! 用于本机联调,接收探索服的战斗请求,按请求种子模拟战斗或按规则返回固定结果
+#[allow(unused_imports)]
+#[macro_use]
+extern crate shared;
+#[macro_use]
+extern crate async_trait;
+#[macro_use]
+extern crate log;
+use battle_proto::msg_id;
+use battle_mock::battle;
+mod channel;
+fn main() -> anyhow::Result<()>{
+    shared::libconfig::config::config_path(Some("configs/battle_mock.ini"));
+    let log_dir = std::env::current_dir()?.display().to_string();
+    shared::logger::init(
+        &log_dir,
+        "BattleMock".to_string(),
+        shared::libconfig::config::get::<i32>("log_trace") == Some(1)).map_err(|e| {error!("failed to initialize logger: {}",e);2usize}).map_err(|_| -> std::io::Error {std::io::ErrorKind::BrokenPipe.into()})?;
+    battle::load_rules()?;
+    shared::server::worker::init(0)?;
+    channel::start_up()?;
+    info!("battle mock started");
+    shared::server::worker::block_on(shared::server::worker::get_shutdown_handler().recv())?;
+    Ok(())
+}
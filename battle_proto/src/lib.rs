@@ -0,0 +1,168 @@
+//! 探索战斗协议及模拟
+//!
+//! 探索服与战斗服共用的消息结构,战斗消息以json传输
+//! 根据种子进行回合制战斗模拟,客户端使用同样的种子和规则计算结果,探索服与战斗服都以模拟结果为准
+use rand::{rngs::StdRng, Rng, SeedableRng};
+///战斗服通道消息id
+pub mod msg_id{
+    ///战斗请求(json)
+    pub const BATTLE_START_REQ: u16 = 2201;
+    ///战斗结果(json)
+    pub const BATTLE_RESULT_SYNC: u16 = 2202;
+}
+///最大战斗回合,超过回合数视为战斗失败
+const MAX_BATTLE_ROUND: u32 = 100;
+///角色状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CharacterState{
+    ///活跃
+    Active = 0,
+    ///重伤
+    Injured = 1,
+    ///不可用
+    Unusable = 2,
+}
+impl Default for CharacterState{    
+    fn default() -> Self { CharacterState::Active }
+}
+impl From<i32> for CharacterState{
+    fn from(v: i32) -> Self {
+        match v {
+            0 => CharacterState::Active,
+            1 => CharacterState::Injured,
+            _ => CharacterState::Unusable,
+        }
+    }
+}
+///参战单位
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BattleUnit{
+    pub id: u32,
+    pub attack: i32,
+    pub defense: i32,
+    pub health: i32,
+    pub max_health: i32,
+}
+///战斗敌人
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BattleEnemy{
+    pub id: u32,
+    pub attack: i32,
+    pub defense: i32,
+    pub health: i32,
+}
+///角色战斗结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BattleCharacterResult{
+    pub id: u32,
+    pub health: i32,
+    pub state: CharacterState,
+}
+///战斗结果
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BattleOutcome{
+    pub win: bool,
+    pub round: u32,
+    pub characters: Vec<BattleCharacterResult>,
+}
+///发往战斗服的战斗请求
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BattleRequest{
+    pub player_id: u64,
+    ///战斗事件uuid
+    pub event_id: u64,
+    pub seed: u64,
+    pub party: Vec<BattleUnit>,
+    pub enemy: BattleEnemy,
+}
+impl BattleRequest{
+    ///按请求的种子模拟战斗
+    #[inline]
+    pub fn simulate(&self) -> BattleOutcome{
+        simulate(&self.party, &self.enemy, self.seed)
+    }
+}
+///战斗服返回的战斗结果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BattleResult{
+    pub player_id: u64,
+    pub event_id: u64,
+    pub outcome: BattleOutcome,
+}
+///单次伤害,伤害浮动90%~110%,最低1点
+#[inline]
+fn damage(rng: &mut StdRng, attack: i32, defense: i32) -> i32{
+    let base = (attack as i64 - defense as i64).max(1);
+    (base * rng.gen_range(90..=110) / 100).clamp(1, i32::MAX as i64) as i32
+}
+///没有敌人配置时按战斗失败处理,角色状态不变
+pub fn lost(party: &[BattleUnit]) -> BattleOutcome{
+    BattleOutcome{
+        win: false,
+        round: 0,
+        characters: party.iter().map(|u| BattleCharacterResult{
+            id: u.id,
+            health: u.health,
+            state: if u.health > 1 { CharacterState::Active } else { CharacterState::Injured },
+        }).collect(),
+    }
+}
+///模拟战斗
+///
+/// 队伍按顺序依次攻击敌人,之后敌人随机攻击一个存活角色,生命值小于等于1视为重伤
+pub fn simulate(party: &[BattleUnit], enemy: &BattleEnemy, seed: u64) -> BattleOutcome{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut units = party.to_vec();
+    let mut enemy_health = enemy.health;
+    let mut round = 0;
+    let mut win = false;
+    while round < MAX_BATTLE_ROUND {
+        round += 1;
+        for unit in units.iter().filter(|u| u.health > 1) {
+            enemy_health -= damage(&mut rng, unit.attack, enemy.defense);
+            if enemy_health <= 0 {
+                win = true;
+                break;
+            }
+        }
+        if win {
+            break;
+        }
+        let alive = units.iter().enumerate().filter(|(_, u)| u.health > 1).map(|(idx, _)| idx).collect::<Vec<_>>();
+        if alive.is_empty() {
+            break;
+        }
+        let target = &mut units[alive[rng.gen_range(0..alive.len())]];
+        target.health = (target.health - damage(&mut rng, enemy.attack, target.defense)).max(1);
+    }
+    BattleOutcome{
+        win,
+        round,
+        characters: units.iter().map(|u| BattleCharacterResult{
+            id: u.id,
+            health: u.health,
+            state: if u.health > 1 { CharacterState::Active } else { CharacterState::Injured },
+        }).collect(),
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_simulate_deterministic(){
+    let party = vec![
+        BattleUnit{ id: 10111, attack: 120, defense: 80, health: 1000, max_health: 1000 },
+        BattleUnit{ id: 10211, attack: 90, defense: 100, health: 800, max_health: 800 },
+    ];
+    let enemy = BattleEnemy{ id: 1, attack: 150, defense: 60, health: 1500 };
+    let first = simulate(&party, &enemy, 0x5eed);
+    assert_eq!(first, simulate(&party, &enemy, 0x5eed));
+    assert!(first.win);
+    assert!(first.characters.iter().all(|c| c.health >= 1 && c.health <= 1000));
+    let weak = vec![BattleUnit{ id: 1, attack: 1, defense: 0, health: 10, max_health: 10 }];
+    let lost = simulate(&weak, &enemy, 1);
+    assert!(!lost.win);
+    assert_eq!(lost.characters[0].state, CharacterState::Injured);
+    //极大的配置数值不会溢出
+    let strong = vec![BattleUnit{ id: 2, attack: i32::MAX, defense: i32::MAX, health: i32::MAX, max_health: i32::MAX }];
+    let huge = BattleEnemy{ id: 2, attack: i32::MAX, defense: i32::MIN, health: i32::MAX };
+    assert!(simulate(&strong, &huge, 7).win);
+}
@@ -0,0 +1,42 @@
+//! 数据库版本迁移
+//!
+//! 迁移脚本编译进程序,启动时按版本号顺序执行未执行过的脚本,
+//! 每个服务使用自己的版本记录表,互不影响
+pub struct Migration{
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+///版本记录表建表语句,mysql和sqlite通用
+pub fn version_table_sql(table: &str) -> String{
+    format!("CREATE TABLE IF NOT EXISTS {} (version BIGINT NOT NULL PRIMARY KEY, name VARCHAR(64) NOT NULL, applied_time BIGINT NOT NULL)", table)
+}
+///当前版本查询语句
+pub fn current_version_sql(table: &str) -> String{
+    format!("SELECT MAX(version) FROM {}", table)
+}
+///记录已执行版本的语句
+pub fn insert_version_sql(table: &str) -> String{
+    format!("INSERT INTO {} (version,name,applied_time) VALUES(?,?,?)", table)
+}
+///当前版本之后需要执行的迁移
+pub fn pending(migrations: &'static [Migration], current: Option<i64>) -> impl Iterator<Item = &'static Migration>{
+    migrations.iter().filter(move |m| m.version > current.unwrap_or(0))
+}
+///校验迁移列表:版本号从1开始递增且脚本不为空
+pub fn check(migrations: &[Migration]) -> bool{
+    migrations.windows(2).all(|w| w[0].version < w[1].version)
+    && migrations.iter().all(|m| m.version > 0 && !m.sql.trim().is_empty())
+}
+#[cfg(test)]
+#[test]
+fn test_pending(){
+    static MIGRATIONS: &[Migration] = &[
+        Migration{ version: 1, name: "init", sql: "SELECT 1" },
+        Migration{ version: 2, name: "next", sql: "SELECT 2" },
+    ];
+    assert!(check(MIGRATIONS));
+    assert_eq!(pending(MIGRATIONS, None).count(), 2);
+    assert_eq!(pending(MIGRATIONS, Some(1)).map(|m| m.version).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(pending(MIGRATIONS, Some(2)).count(), 0);
+}
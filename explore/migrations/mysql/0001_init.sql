@@ -0,0 +1,59 @@
+-- 探索服初始表结构
+CREATE TABLE IF NOT EXISTS db_explore (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    player_id BIGINT UNSIGNED NOT NULL,
+    explore_id INT UNSIGNED NOT NULL,
+    -- 0 进行中 1 完成 2 取消
+    state INT NOT NULL DEFAULT 0,
+    max_event INT NOT NULL DEFAULT 0,
+    finished_event INT NOT NULL DEFAULT 0,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finish_time TIMESTAMP NULL DEFAULT NULL,
+    token VARCHAR(128) NOT NULL DEFAULT '',
+    food INT NOT NULL DEFAULT 0,
+    `position` JSON NULL,
+    origin_position JSON NULL,
+    -- 已探索格子位图
+    explored_map BLOB NULL,
+    PRIMARY KEY (id),
+    KEY idx_player_state (player_id, state)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS db_finished_event (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    player_id BIGINT UNSIGNED NOT NULL,
+    scene_type INT NOT NULL DEFAULT 0,
+    scene_id BIGINT UNSIGNED NOT NULL,
+    event_id INT UNSIGNED NOT NULL,
+    event_type INT NOT NULL,
+    `position` JSON NULL,
+    progress_event INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (id),
+    KEY idx_player_scene (player_id, scene_type, scene_id),
+    KEY idx_player_event (player_id, event_type, event_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS global_explore_variables (
+    player_id BIGINT UNSIGNED NOT NULL,
+    scene_type INT NOT NULL DEFAULT 0,
+    scene_id INT UNSIGNED NOT NULL,
+    variable_type INT NOT NULL,
+    `value` INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (player_id, scene_type, scene_id, variable_type)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS db_unique_event (
+    player_id BIGINT UNSIGNED NOT NULL,
+    event_type INT NOT NULL,
+    event_id INT UNSIGNED NOT NULL,
+    PRIMARY KEY (player_id, event_type, event_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS db_explore_character (
+    explore_id BIGINT UNSIGNED NOT NULL,
+    role_id INT UNSIGNED NOT NULL,
+    health INT NOT NULL DEFAULT 0,
+    max_health INT NOT NULL DEFAULT 0,
+    state INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (explore_id, role_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
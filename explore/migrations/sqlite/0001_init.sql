@@ -0,0 +1,57 @@
+-- 探索服初始表结构
+CREATE TABLE IF NOT EXISTS db_explore (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    player_id INTEGER NOT NULL,
+    explore_id INTEGER NOT NULL,
+    -- 0 进行中 1 完成 2 取消
+    state INTEGER NOT NULL DEFAULT 0,
+    max_event INTEGER NOT NULL DEFAULT 0,
+    finished_event INTEGER NOT NULL DEFAULT 0,
+    create_time TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finish_time TEXT NULL,
+    token TEXT NOT NULL DEFAULT '',
+    food INTEGER NOT NULL DEFAULT 0,
+    `position` TEXT NULL,
+    origin_position TEXT NULL,
+    -- 已探索格子位图
+    explored_map BLOB NULL
+);
+CREATE INDEX IF NOT EXISTS idx_explore_player_state ON db_explore (player_id, state);
+
+CREATE TABLE IF NOT EXISTS db_finished_event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    player_id INTEGER NOT NULL,
+    scene_type INTEGER NOT NULL DEFAULT 0,
+    scene_id INTEGER NOT NULL,
+    event_id INTEGER NOT NULL,
+    event_type INTEGER NOT NULL,
+    `position` TEXT NULL,
+    progress_event INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_finished_event_scene ON db_finished_event (player_id, scene_type, scene_id);
+CREATE INDEX IF NOT EXISTS idx_finished_event_event ON db_finished_event (player_id, event_type, event_id);
+
+CREATE TABLE IF NOT EXISTS global_explore_variables (
+    player_id INTEGER NOT NULL,
+    scene_type INTEGER NOT NULL DEFAULT 0,
+    scene_id INTEGER NOT NULL,
+    variable_type INTEGER NOT NULL,
+    `value` INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (player_id, scene_type, scene_id, variable_type)
+);
+
+CREATE TABLE IF NOT EXISTS db_unique_event (
+    player_id INTEGER NOT NULL,
+    event_type INTEGER NOT NULL,
+    event_id INTEGER NOT NULL,
+    PRIMARY KEY (player_id, event_type, event_id)
+);
+
+CREATE TABLE IF NOT EXISTS db_explore_character (
+    explore_id INTEGER NOT NULL,
+    role_id INTEGER NOT NULL,
+    health INTEGER NOT NULL DEFAULT 0,
+    max_health INTEGER NOT NULL DEFAULT 0,
+    state INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (explore_id, role_id)
+);
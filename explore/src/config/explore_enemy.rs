@@ -0,0 +1,23 @@
+//! 探索敌人配置
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+///战斗敌人,直接作为战斗请求发往战斗服
+pub use battle_proto::BattleEnemy as ExploreEnemyConfig;
+static ENEMY_CONFIGS: Lazy<RwLock<BTreeMap<u32, ExploreEnemyConfig>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///读取敌人配置
+pub fn read_config(path: &str) -> anyhow::Result<BTreeMap<u32, ExploreEnemyConfig>>{
+    let content = std::fs::read_to_string(path).map_err(|e| logthrow!(e, e))?;
+    let list = serde_json::from_str::<Vec<ExploreEnemyConfig>>(&content)?;
+    let configs = list.into_iter().map(|c| (c.id, c)).collect::<BTreeMap<_,_>>();
+    info!("load {} explore enemies from {}", configs.len(), path);
+    Ok(configs)
+}
+///替换敌人配置
+pub(super) fn set_configs(configs: BTreeMap<u32, ExploreEnemyConfig>){
+    *ENEMY_CONFIGS.write().unwrap() = configs;
+}
+#[inline]
+pub fn get_enemy(id: u32) -> Option<ExploreEnemyConfig>{
+    ENEMY_CONFIGS.read().unwrap().get(&id).cloned()
+}
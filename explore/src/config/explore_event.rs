@@ -0,0 +1,51 @@
+//! 探索事件配置
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+///探索事件摆放配置
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExploreEventConfig{
+    pub id: u32,
+    ///所属探索配置id
+    pub explore_id: u32,
+    pub event_id: u32,
+    pub event_type: i32,
+    pub x: i32,
+    pub y: i32,
+    ///触发范围,0表示玩家需要走到事件点上
+    #[serde(default)]
+    pub trigger_range: u32,
+    ///是否进度事件
+    #[serde(default)]
+    pub progress_event: bool,
+    ///战斗事件的敌人id,0为非战斗事件
+    #[serde(default)]
+    pub enemy_id: u32,
+    ///完成事件获得的物品id,0为没有物品
+    #[serde(default)]
+    pub item_id: u32,
+    ///完成事件获得的物品数量
+    #[serde(default)]
+    pub item_count: u32,
+}
+///探索配置id -> 事件列表
+static EVENT_CONFIGS: Lazy<RwLock<BTreeMap<u32, Vec<ExploreEventConfig>>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///读取事件配置
+pub fn read_config(path: &str) -> anyhow::Result<BTreeMap<u32, Vec<ExploreEventConfig>>>{
+    let content = std::fs::read_to_string(path).map_err(|e| logthrow!(e, e))?;
+    let list = serde_json::from_str::<Vec<ExploreEventConfig>>(&content)?;
+    let mut configs: BTreeMap<u32, Vec<ExploreEventConfig>> = BTreeMap::new();
+    for config in list {
+        configs.entry(config.explore_id).or_default().push(config);
+    }
+    info!("load {} explore event groups from {}", configs.len(), path);
+    Ok(configs)
+}
+///替换事件配置
+pub(super) fn set_configs(configs: BTreeMap<u32, Vec<ExploreEventConfig>>){
+    *EVENT_CONFIGS.write().unwrap() = configs;
+}
+///获取探索的事件摆放
+pub fn get_events(explore_id: u32) -> Vec<ExploreEventConfig>{
+    EVENT_CONFIGS.read().unwrap().get(&explore_id).cloned().unwrap_or_default()
+}
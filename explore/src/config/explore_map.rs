@@ -0,0 +1,104 @@
+//! 探索地图配置
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+use shared::map::{Map, MapBuilder, Point2};
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+pub struct MapPoint{
+    pub x: i32,
+    pub y: i32,
+}
+///地形,进入该格子时额外消耗
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+pub struct TerrainConfig{
+    pub x: i32,
+    pub y: i32,
+    ///地形类型
+    #[serde(default)]
+    pub kind: u32,
+    ///额外移动消耗
+    #[serde(default)]
+    pub cost: i32,
+}
+///探索地图
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExploreMapConfig{
+    ///探索配置id
+    pub id: u32,
+    pub map_id: u32,
+    pub width: u32,
+    pub height: u32,
+    ///六边形地图
+    #[serde(default)]
+    pub hex: bool,
+    ///障碍格子id
+    #[serde(default)]
+    pub barriers: Vec<u16>,
+    ///出生点
+    pub spawn_points: Vec<MapPoint>,
+    #[serde(default)]
+    pub terrain: Vec<TerrainConfig>,
+}
+impl ExploreMapConfig{
+    ///生成地图
+    pub fn build(&self) -> Map{
+        MapBuilder::new(self.map_id as _, self.width as _, self.height as _, self.hex)
+            .with_barriers(self.barriers.iter().map(|id| *id as _).collect())
+            .build()
+    }
+    ///地图上的有效格子,不在地图上或者为障碍时返回None
+    pub fn walkable_point(&self, map: &Map, x: i32, y: i32) -> Option<Point2>{
+        let mut point = Point2::new(x, y);
+        map.bind_point(&mut point);
+        if map.iter().find(|p| p.id() == point.id()).is_none() || self.barriers.contains(&(point.id() as u16)){
+            return None;
+        }
+        Some(point)
+    }
+    ///校验配置
+    fn validate(&self) -> anyhow::Result<()>{
+        if self.width == 0 || self.height == 0 || self.spawn_points.is_empty(){
+            error!("explore map {} has invalid size {:?} or no spawn point", self.id, (self.width, self.height));
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+        let map = self.build();
+        for point in self.spawn_points.iter() {
+            if self.walkable_point(&map, point.x, point.y).is_none(){
+                error!("explore map {} spawn point {:?} is not walkable", self.id, point);
+                return shared::error::any_err(std::io::ErrorKind::InvalidData);
+            }
+        }
+        for terrain in self.terrain.iter() {
+            if self.walkable_point(&map, terrain.x, terrain.y).is_none(){
+                error!("explore map {} terrain {:?} is not walkable", self.id, terrain);
+                return shared::error::any_err(std::io::ErrorKind::InvalidData);
+            }
+        }
+        Ok(())
+    }
+}
+static MAP_CONFIGS: Lazy<RwLock<BTreeMap<u32, ExploreMapConfig>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///读取地图配置
+pub fn read_config(path: &str) -> anyhow::Result<BTreeMap<u32, ExploreMapConfig>>{
+    let content = std::fs::read_to_string(path).map_err(|e| logthrow!(e, e))?;
+    let list = serde_json::from_str::<Vec<ExploreMapConfig>>(&content)?;
+    let mut configs = BTreeMap::new();
+    for config in list {
+        config.validate()?;
+        if configs.contains_key(&config.id){
+            error!("duplicated explore map {}", config.id);
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+        configs.insert(config.id, config);
+    }
+    info!("load {} explore maps from {}", configs.len(), path);
+    Ok(configs)
+}
+///替换地图配置
+pub(super) fn set_configs(configs: BTreeMap<u32, ExploreMapConfig>){
+    *MAP_CONFIGS.write().unwrap() = configs;
+}
+#[inline]
+pub fn get_map(explore_id: u32) -> Option<ExploreMapConfig>{
+    MAP_CONFIGS.read().unwrap().get(&explore_id).cloned()
+}
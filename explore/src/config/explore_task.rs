@@ -0,0 +1,69 @@
+//! 探索任务配置
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+///任务目标
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObjectiveConfig{
+    ///到达指定格子
+    ReachTile{ x: i32, y: i32 },
+    ///完成指定数量的进度事件
+    ProgressEvents{ count: u32 },
+    ///击败指定敌人
+    DefeatEnemy{
+        enemy_id: u32,
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+    ///收集物品,物品由完成事件获得
+    CollectItem{ item_id: u32, count: u32 },
+}
+fn default_count() -> u32{ 1 }
+impl ObjectiveConfig{
+    ///目标进度
+    pub fn target(&self) -> u32{
+        match self {
+            ObjectiveConfig::ReachTile{..} => 1,
+            ObjectiveConfig::ProgressEvents{ count } => *count,
+            ObjectiveConfig::DefeatEnemy{ count, .. } => *count,
+            ObjectiveConfig::CollectItem{ count, .. } => *count,
+        }
+    }
+}
+///探索任务
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExploreTaskConfig{
+    pub id: u32,
+    ///限定的探索配置id,0为不限定
+    #[serde(default)]
+    pub explore_id: u32,
+    pub objectives: Vec<ObjectiveConfig>,
+}
+static TASK_CONFIGS: Lazy<RwLock<BTreeMap<u32, ExploreTaskConfig>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///读取任务配置
+pub fn read_config(path: &str) -> anyhow::Result<BTreeMap<u32, ExploreTaskConfig>>{
+    let content = std::fs::read_to_string(path).map_err(|e| logthrow!(e, e))?;
+    let list = serde_json::from_str::<Vec<ExploreTaskConfig>>(&content)?;
+    let mut configs = BTreeMap::new();
+    for task in list {
+        if task.objectives.is_empty() || task.objectives.iter().any(|o| o.target() == 0){
+            error!("explore task {} has no valid objective", task.id);
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+        if configs.insert(task.id, task).is_some(){
+            error!("duplicate explore task in {}", path);
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+    }
+    info!("load {} explore tasks from {}", configs.len(), path);
+    Ok(configs)
+}
+///替换任务配置
+pub(super) fn set_configs(configs: BTreeMap<u32, ExploreTaskConfig>){
+    *TASK_CONFIGS.write().unwrap() = configs;
+}
+#[inline]
+pub fn get_task(id: u32) -> Option<ExploreTaskConfig>{
+    TASK_CONFIGS.read().unwrap().get(&id).cloned()
+}
@@ -0,0 +1,93 @@
+//! 探索服配置表
+pub mod explore_event;
+pub mod explore_enemy;
+pub mod explore_map;
+pub mod explore_task;
+use std::collections::BTreeMap;
+///探索配置表,全部读取并校验通过后才替换当前配置
+pub struct ExploreTables{
+    pub events: BTreeMap<u32, Vec<explore_event::ExploreEventConfig>>,
+    pub maps: BTreeMap<u32, explore_map::ExploreMapConfig>,
+    pub enemies: BTreeMap<u32, explore_enemy::ExploreEnemyConfig>,
+    pub tasks: BTreeMap<u32, explore_task::ExploreTaskConfig>,
+}
+impl ExploreTables{
+    ///读取配置目录下的探索配置表
+    pub fn read(dir: &str) -> anyhow::Result<Self>{
+        Ok(Self{
+            events: explore_event::read_config(&format!("{}/ExploreEvent.json", dir))?,
+            maps: explore_map::read_config(&format!("{}/ExploreMap.json", dir))?,
+            enemies: explore_enemy::read_config(&format!("{}/ExploreEnemy.json", dir))?,
+            tasks: explore_task::read_config(&format!("{}/ExploreTask.json", dir))?,
+        })
+    }
+    ///校验配置表之间的引用
+    pub fn validate(&self) -> anyhow::Result<()>{
+        for event in self.events.values().flatten() {
+            if event.enemy_id > 0 && !self.enemies.contains_key(&event.enemy_id){
+                error!("explore event {} references missing enemy {}", event.id, event.enemy_id);
+                return shared::error::any_err(std::io::ErrorKind::InvalidData);
+            }
+            let map = match self.maps.get(&event.explore_id) {
+                Some(map) => map,
+                None => {
+                    error!("explore event {} references missing map {}", event.id, event.explore_id);
+                    return shared::error::any_err(std::io::ErrorKind::InvalidData);
+                }
+            };
+            if map.walkable_point(&map.build(), event.x, event.y).is_none(){
+                error!("explore event {} at {:?} is not walkable on map {}", event.id, (event.x, event.y), event.explore_id);
+                return shared::error::any_err(std::io::ErrorKind::InvalidData);
+            }
+        }
+        for task in self.tasks.values() {
+            for objective in task.objectives.iter() {
+                match objective {
+                    explore_task::ObjectiveConfig::DefeatEnemy{ enemy_id, .. } if !self.enemies.contains_key(enemy_id) => {
+                        error!("explore task {} references missing enemy {}", task.id, enemy_id);
+                        return shared::error::any_err(std::io::ErrorKind::InvalidData);
+                    },
+                    explore_task::ObjectiveConfig::ReachTile{ x, y } if task.explore_id > 0 => {
+                        let walkable = self.maps.get(&task.explore_id).map(|map| map.walkable_point(&map.build(), *x, *y).is_some());
+                        if walkable != Some(true){
+                            error!("explore task {} target {:?} is not walkable on map {}", task.id, (x, y), task.explore_id);
+                            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+    ///替换当前配置
+    pub fn apply(self){
+        explore_event::set_configs(self.events);
+        explore_map::set_configs(self.maps);
+        explore_enemy::set_configs(self.enemies);
+        explore_task::set_configs(self.tasks);
+    }
+}
+///加载仓库中的示例配置
+#[cfg(test)]
+pub(crate) fn load_samples(){
+    let tables = ExploreTables::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../configs/json")).unwrap();
+    tables.validate().unwrap();
+    tables.apply();
+}
+#[cfg(test)]
+#[test]
+fn test_sample_configs(){
+    load_samples();
+    assert_eq!(explore_event::get_events(1).len(), 4);
+    assert!(explore_map::get_map(1).is_some());
+    assert!(explore_enemy::get_enemy(2).is_some());
+    assert_eq!(explore_task::get_task(1).map(|task| task.objectives.len()), Some(3));
+}
+#[cfg(test)]
+#[test]
+fn test_invalid_tables_not_applied(){
+    let mut tables = ExploreTables::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../configs/json")).unwrap();
+    tables.enemies.clear();
+    assert!(tables.validate().is_err());
+}
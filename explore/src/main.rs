@@ -10,6 +10,7 @@ extern crate log;
 extern crate anyhow;
 mod server;
 mod msg_id;
+mod config;
 fn main() -> anyhow::Result<()>{
     // let mut req = shared::proto::C2PMsgLogin::new();
     // req.account = "111".to_string();
@@ -7,4 +7,22 @@ pub const EXPLORE_MOVE_REQ: u16 = 2004;
 ///移动答复
 pub const EXPLORE_MOVE_RESP: u16 = 2005;
 pub const EXPLORE_BATTLE_RESULT_REQ: u16 = 2006;
-pub const EXPLORE_BATTLE_RESULT_RESP: u16 = 2007;
\ No newline at end of file
+pub const EXPLORE_BATTLE_RESULT_RESP: u16 = 2007;
+///放弃探索请求
+pub const EXPLORE_QUIT_REQ: u16 = 2008;
+///放弃探索答复(json,ExploreQuitResp)
+pub const EXPLORE_QUIT_RESP: u16 = 2009;
+///平台服放弃探索请求(平台服通道消息)
+pub const ABANDON_EXPLORE_REQ: u16 = 2101;
+///平台服请求负载(平台服通道消息)
+pub const EXPLORE_LOAD_REQ: u16 = 2102;
+///负载上报(平台服通道消息,json)
+pub const EXPLORE_LOAD_SYNC: u16 = 2103;
+///向平台服注册(平台服通道消息,json)
+pub const EXPLORE_REGISTER_REQ: u16 = 2104;
+///平台服标识(平台服通道消息,json)
+pub const PLAT_SERVER_SYNC: u16 = 2105;
+///探索结算(平台服通道消息,json)
+pub const EXPLORE_SETTLE_SYNC: u16 = 2106;
+///战斗请求及结果(战斗服通道消息,json),与战斗服共用
+pub use battle_proto::msg_id::{BATTLE_START_REQ, BATTLE_RESULT_SYNC};
\ No newline at end of file
@@ -0,0 +1,67 @@
+//! 后台管理接口
+//!
+//! 挂载在 bind_web_port 的 http 服务上,用于线上排查玩家探索问题
+use actix_web::{get, post, web, HttpResponse, Responder};
+use futures::future::join_all;
+use super::explore::command::ExploreBrief;
+///注册后台接口
+pub fn config(cfg: &mut web::ServiceConfig){
+    cfg.service(list_explores)
+        .service(bearing)
+        .service(explore_detail)
+        .service(explore_by_uuid)
+        .service(close_explore)
+        .service(metrics);
+}
+///进行中的探索列表
+#[get("/explores")]
+async fn list_explores() -> impl Responder {
+    let players = super::entry::explore_players();
+    let explores = join_all(players.into_iter().map(super::entry::explore_snapshot)).await
+        .iter()
+        .flatten()
+        .map(ExploreBrief::from)
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(explores)
+}
+///当前负载
+#[get("/bearing")]
+async fn bearing() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "bearing": super::entry::get_bearing() }))
+}
+///探索详细信息
+#[get("/explores/{player_id}")]
+async fn explore_detail(player_id: web::Path<u64>) -> impl Responder {
+    match super::entry::explore_snapshot(player_id.into_inner()).await {
+        Some(snapshot) => HttpResponse::Ok().json(snapshot),
+        None => HttpResponse::NotFound().json("explore not found"),
+    }
+}
+///根据探索uuid查询探索
+#[get("/explores/uuid/{uuid}")]
+async fn explore_by_uuid(uuid: web::Path<u64>) -> impl Responder {
+    let snapshot = match super::entry::explore_player_by_uuid(uuid.into_inner()) {
+        Some(player_id) => super::entry::explore_snapshot(player_id).await,
+        None => None,
+    };
+    match snapshot {
+        Some(snapshot) => HttpResponse::Ok().json(snapshot),
+        None => HttpResponse::NotFound().json("explore not found"),
+    }
+}
+///运行指标
+#[get("/metrics")]
+async fn metrics() -> impl Responder {
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(super::metrics::render())
+}
+///强制关闭探索
+#[post("/explores/{player_id}/close")]
+async fn close_explore(player_id: web::Path<u64>) -> impl Responder {
+    let player_id = player_id.into_inner();
+    if super::entry::close_explore(player_id).await {
+        info!("explore of player {} closed by admin", player_id);
+        HttpResponse::Ok().json("explore closed")
+    } else {
+        HttpResponse::NotFound().json("explore not found")
+    }
+}
@@ -1,8 +1,171 @@
 //!战斗服通讯通道
-#![allow(unused)]
-use shared::{AsyncSessionHandler, Transporter, AsyncSocketHandler, server::{channel::{self, ServiceChannel, ChannelState}}, timer::IntervalTimer};
+//!
+//! 战斗消息以json放在 StringValue 中传输,未配置战斗服或通道断开时战斗在本地模拟
+//! 战斗服需按请求中的种子执行与 battle::simulate 相同的模拟,客户端上报的结果与之比对
+use once_cell::sync::OnceCell;
+use protobuf::well_known_types::StringValue;
+use shared::{SessionTransport, SyncSessionHandler, boxed::MutexArc, server::{channel::{self, ServiceChannel, ChannelState}}, timer::IntervalTimer};
+use crate::server::explore::battle::{BattleRequest, BattleResult};
+///通道更新间隔
+const BATTLE_CHANNEL_UPDATE_INTERVAL: u64 = 50;
+static BATTLE_CLIENT: OnceCell<MutexArc<BattleChannel>> = OnceCell::new();
 pub struct BattleChannel{
-    handler: AsyncSessionHandler<()>,
+    handler: SyncSessionHandler<()>,
     heart_timer: IntervalTimer,
-    state: ChannelState
-}
\ No newline at end of file
+    state: ChannelState,
+    reconnect_handler: crossbeam::channel::Receiver<SyncSessionHandler<()>>,
+    reconnect_callback: crossbeam::channel::Sender<SyncSessionHandler<()>>,
+}
+impl BattleChannel{
+    ///战斗服地址,未配置时返回None
+    fn addr() -> Option<String>{
+        let ip = shared::libconfig::config::get_str("battle_server_ip")?;
+        let port = shared::libconfig::config::get::<i32>("battle_server_port")?;
+        Some(format!("{}:{}", ip, port))
+    }
+    ///连接战斗服,直到连接成功
+    fn connect(addr: String) -> Self{
+        let handler = loop {
+            if let Ok(conn) = channel::connect(addr.clone()){
+                break conn;
+            }
+            warn!("fail to connect to battle channel {}, try after 3 seconds",addr);
+            std::thread::sleep(std::time::Duration::from_millis(3000));
+        };
+        info!("battle_channel connect success,session id {}",handler.id());
+        Self::new(handler, ChannelState::Connected)
+    }
+    pub fn reconnect_async(&mut self) -> anyhow::Result<()>{
+        let addr = Self::addr().ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+        super::reconnect_async("battle_channel", addr, self.reconnect_callback.clone());
+        self.state =  ChannelState::Reconnecting;
+        Ok(())
+    }
+    fn handle_reconnect(&mut self){
+        if let Ok(handler) = self.reconnect_handler.try_recv(){
+            self.handler = handler;
+            self.state =  ChannelState::Connected;
+            self.hand_shake().map_err(|e| logthrow!(e,"battle channel reconnect hand_shake fail",())).ok();
+        }
+    }
+    #[inline]
+    fn reconnecting(&self) -> bool{
+        self.state == ChannelState::Reconnecting
+    }
+}
+impl ServiceChannel<()> for BattleChannel{
+    #[inline]
+    fn handler_mut(&mut self) -> &mut SyncSessionHandler<()> {
+        &mut self.handler
+    }
+    #[inline]
+    fn handler(&self) -> &SyncSessionHandler<()> {
+        &self.handler
+    }
+
+    #[inline]
+    fn state(&self) -> channel::ChannelState {
+        self.state
+    }
+
+    fn new(handler: SyncSessionHandler<()>, state: ChannelState) -> Self {
+        let (tx,rx) = crossbeam::channel::bounded(1);
+        Self{
+            handler,
+            heart_timer: IntervalTimer::new(12*1000),
+            state,
+            reconnect_handler: rx,
+            reconnect_callback: tx,
+        }
+    }
+    fn update(&mut self, diff: i64){
+        if self.reconnecting() {
+            self.handle_reconnect();
+            if self.reconnecting(){
+                return;
+            }
+        }
+        self.handle_event();
+        self.heart_timer.update(diff);
+        if self.heart_timer.passed(){
+            self.heart_timer.reset();
+            self.heartbeat().ok();
+        }
+    }
+
+    fn on_packet(&mut self, packet: shared::proto::PackBuffer)-> anyhow::Result<()> {
+        match packet.header().sub_code() as u16 {
+            crate::msg_id::BATTLE_RESULT_SYNC => {
+                let pack = packet.unpack::<StringValue>().map_err(|_| shared::error::unpack_err())?;
+                let result = serde_json::from_str::<BattleResult>(pack.get_value()).map_err(|e| logthrow!(e, e))?;
+                crate::server::entry::on_battle_result(result);
+            },
+            opcode => {
+                warn!("unexpected opcode {} from battle channel",opcode);
+            }
+        }
+        Ok(())
+    }
+    #[inline]
+    fn heart_timer(&mut self) -> &mut IntervalTimer {
+        &mut self.heart_timer
+    }
+
+    #[inline]
+    fn set_state(&mut self, s: ChannelState) {
+        self.state = s;
+    }
+    #[inline]
+    fn client_type(&self) -> shared::proto::ChannelClientType {shared::proto::ChannelClientType::ExploreServer }
+}
+///启动战斗服通道,连接与更新在独立线程中进行
+pub fn start_up() -> anyhow::Result<()>{
+    let addr = match BattleChannel::addr() {
+        Some(addr) => addr,
+        None => {
+            info!("battle server not configured, battles will be simulated locally");
+            return Ok(());
+        }
+    };
+    std::thread::Builder::new().name("battle_channel".to_string()).spawn(move || {
+        let channel = BattleChannel::connect(addr);
+        channel.hand_shake().map_err(|e| logthrow!(e,"battle channel hand_shake fail",())).ok();
+        if BATTLE_CLIENT.set(MutexArc::new(channel)).is_err() {
+            error!("battle channel started twice");
+            return;
+        }
+        let mut last_time = shared::get_current_ms();
+        while !crate::server::start::stopped() {
+            let now = shared::get_current_ms();
+            update((now - last_time).max(0));
+            last_time = now;
+            std::thread::sleep(std::time::Duration::from_millis(BATTLE_CHANNEL_UPDATE_INTERVAL));
+        }
+    })?;
+    Ok(())
+}
+fn update(diff: i64){
+    let mut battle_channel = BATTLE_CLIENT.get().unwrap().get_mut(None).unwrap();
+    battle_channel.update(diff);
+    if battle_channel.state() == ChannelState::Disconnected{
+        battle_channel.reconnect_async().ok();
+    }
+}
+///发送战斗请求,通道不可用时返回错误
+pub fn send_battle(request: &BattleRequest) -> anyhow::Result<()>{
+    let channel = match BATTLE_CLIENT.get() {
+        Some(channel) => channel.get(),
+        None => return shared::error::any_err(std::io::ErrorKind::NotConnected),
+    };
+    if channel.state() != ChannelState::Connected {
+        return shared::error::any_err(std::io::ErrorKind::NotConnected);
+    }
+    let mut msg = StringValue::new();
+    msg.set_value(serde_json::to_string(request)?);
+    channel.send_msg(SessionTransport::new(
+        shared::proto::proto_code::DEFAULT_MAIN_CODE,
+        crate::msg_id::BATTLE_START_REQ,
+        0,
+        Box::new(msg))).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+    Ok(())
+}
@@ -21,6 +21,9 @@ pub fn start_up() ->anyhow::Result<()>{
 }
 pub fn send_msg(msg: SessionTransport<()>, session_id: usize) -> anyhow::Result<()>{
     info!("send msg {:?} to channel {}",msg,session_id);    
+    if super::plat_channel::is_plat_channel(session_id) {
+        return super::plat_channel::send_msg(msg, session_id);
+    }
     CHANNEL.get().unwrap().send((session_id, msg))?;
     Ok(())
 }
\ No newline at end of file
@@ -1,5 +1,11 @@
 
 use shared::{AsyncSessionHandler, AsyncSocketHandler, AsyncSocketSendHandler, SessionTransport, TransportReceiver, Transporter, proto::PackBuffer, server::{channel::{AsyncServiceChannel, AsyncServiceDataHandler, ChannelState, DefaultAsyncServiceDataHandler}}, timer::IntervalTimer};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+///通道心跳超时时间
+const CHANNEL_HEART_TIMEOUT: i64 = 30 * 1000;
+///心跳检测间隔
+const CHANNEL_HEART_CHECK_INTERVAL: u64 = 1000;
 ///本地服务远程会话
 pub struct ChannelSession{
     handler: AsyncSessionHandler<()>,
@@ -9,10 +15,92 @@ pub struct ChannelSession{
     channel_handler: AsyncSocketSendHandler<()>,
     channel_recv: Option<AsyncSocketHandler<()>>, 
     transporter: Option<Transporter<()>>,
-    transport_handler: Option<TransportReceiver<()>>
+    transport_handler: Option<TransportReceiver<()>>,
+    ///最后一次收到平台服消息的时间
+    active_time: Arc<AtomicI64>,
+    closed: Arc<AtomicBool>,
 }
 
+///负载上报,注册时同样使用
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ExploreLoad{
+    server_id: u32,
+    bearing: usize,
+    server_ip: String,
+    server_port: i32,
+    ///最大探索数量,0为不限制
+    capacity: usize,
+}
+impl ExploreLoad{
+    ///当前负载
+    pub(crate) fn current() -> Self{
+        Self{
+            server_id: shared::libconfig::config::get("server_id").unwrap_or_default(),
+            bearing: crate::server::entry::get_bearing(),
+            server_ip: shared::libconfig::config::get_str("explore_server_ip").unwrap_or_default(),
+            server_port: shared::libconfig::config::get("explore_server_port").unwrap_or_default(),
+            capacity: shared::libconfig::config::get("capacity").unwrap_or_default(),
+        }
+    }
+    ///打包为json消息
+    pub(crate) fn to_value(&self) -> anyhow::Result<protobuf::well_known_types::StringValue>{
+        let mut msg = protobuf::well_known_types::StringValue::new();
+        msg.set_value(serde_json::to_string(self)?);
+        Ok(msg)
+    }
+}
+///探索结算同步,平台服按探索id去重,重复的同步不会重复发放奖励
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ExploreSettleSync{
+    pub player_id: u64,
+    pub explore_uuid: u64,
+    ///探索结果(ExploreResult)
+    pub result: i32,
+}
+impl ExploreSettleSync{
+    ///打包为json消息
+    pub(crate) fn to_msg(&self) -> anyhow::Result<SessionTransport<()>>{
+        let mut msg = protobuf::well_known_types::StringValue::new();
+        msg.set_value(serde_json::to_string(self)?);
+        Ok(SessionTransport::new(
+            shared::proto::proto_code::DEFAULT_MAIN_CODE,
+            crate::msg_id::EXPLORE_SETTLE_SYNC,
+            0,
+            Box::new(msg)))
+    }
+}
 impl ChannelSession{
+    ///向平台服上报负载及玩家连接地址
+    fn sync_load(&mut self, packet: &PackBuffer) -> anyhow::Result<()>{
+        self.handler.send(SessionTransport::new(
+            shared::proto::proto_code::DEFAULT_MAIN_CODE,
+            crate::msg_id::EXPLORE_LOAD_SYNC,
+            packet.header().squence(),
+            Box::new(ExploreLoad::current().to_value()?)))?;
+        Ok(())
+    }
+    #[inline]
+    fn refresh_active(&self){
+        self.active_time.store(shared::time::get_current_ms(), Ordering::Relaxed);
+    }
+    ///心跳检测,超时后断开通道
+    fn start_heart_check(session_id: usize, active_time: Arc<AtomicI64>, closed: Arc<AtomicBool>){
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(CHANNEL_HEART_CHECK_INTERVAL)).await;
+                if closed.load(Ordering::Relaxed) {
+                    break;
+                }
+                let idle = shared::time::get_current_ms() - active_time.load(Ordering::Relaxed);
+                if idle >= CHANNEL_HEART_TIMEOUT {
+                    warn!("explore channel {} heartbeat timeout, idle {} ms", session_id, idle);
+                    crate::server::metrics::inc_channel_heartbeat_timeout();
+                    super::channel_service::send_msg(SessionTransport::disconnect(), session_id).map_err(|e| logthrow!(e,e)).ok();
+                    break;
+                }
+            }
+        });
+    }
 }
 pub struct ChannelDataSession{
     transport_handler: TransportReceiver<()>
@@ -41,26 +129,40 @@ impl AsyncServiceChannel<SessionTransport<()>, ChannelDataSession, ()> for Chann
     fn new(handler: AsyncSessionHandler<()>, state: ChannelState) -> Self {
         let (tx,rx) = tokio::sync::mpsc::unbounded_channel();
         let (sh,rh) = tokio::sync::mpsc::unbounded_channel();
+        let active_time = Arc::new(AtomicI64::new(shared::time::get_current_ms()));
+        let closed = Arc::new(AtomicBool::new(false));
+        Self::start_heart_check(handler.id(), active_time.clone(), closed.clone());
         Self{
             handler, 
-            heart_timer: IntervalTimer::new(30*1000),
+            heart_timer: IntervalTimer::new(CHANNEL_HEART_TIMEOUT),
             state,
             channele_type: shared::proto::ChannelClientType::UnDefined,
             channel_handler: tx.into(), 
             channel_recv: rx.into(),
             transporter: sh.into(), 
             transport_handler: rh.into(),
+            active_time,
+            closed,
         }
     }
 
     async fn on_packet(&mut self, packet: PackBuffer)-> anyhow::Result<()> {
+        self.refresh_active();
         let header = packet.header().clone();
         info!("handle explore channel msg {}",header.sub_code());
         match header.sub_code() as u16{
-            //创建探索
-            shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_REQ => {
+            //创建探索/战斗结果/放弃探索
+            shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_REQ |
+            shared::proto::proto_code::msg_id_es_ps::FIGHT_SUCCESS_RESP |
+            crate::msg_id::ABANDON_EXPLORE_REQ => {
                 crate::server::entry::on_channel_msg(crate::server::entry::ServerChannelEvent::ChannelMsg((self.session_id(),packet))).await?;
             },
+            crate::msg_id::EXPLORE_LOAD_REQ => self.sync_load(&packet)?,
+            crate::msg_id::PLAT_SERVER_SYNC => {
+                for msg in super::plat_route::on_identity(self.session_id(), &packet)? {
+                    self.handler.send(msg)?;
+                }
+            },
             opcode => {
                 warn!("unexpected opcode {} from channel {:?}",opcode,self.client_type());
             }
@@ -68,8 +170,10 @@ impl AsyncServiceChannel<SessionTransport<()>, ChannelDataSession, ()> for Chann
         Ok(())
     }
 
+    ///收到心跳时重置计时器,同时记录活跃时间
     #[inline]
     fn heart_timer(&mut self) -> &mut IntervalTimer {
+        self.refresh_active();
         &mut self.heart_timer
     }
 
@@ -111,6 +215,8 @@ impl AsyncServiceChannel<SessionTransport<()>, ChannelDataSession, ()> for Chann
     }
 
     fn on_close(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+        super::plat_route::unbind(self.session_id());
         super::channel_service::send_msg(SessionTransport::disconnect(), self.session_id()).map_err(|e| logthrow!(e,e)).ok();
     }
 }
\ No newline at end of file
@@ -1,7 +1,28 @@
 //! 服务通讯channel
 #![allow(unused)]
-use shared::{SessionHandler, server::{this_channel::ThisChannel}};
+use shared::{SessionHandler, SyncSessionHandler, server::{this_channel::ThisChannel}};
 
 pub mod battle_channel;
 pub mod channel_service;
-mod channel_session;
\ No newline at end of file
+pub mod plat_channel;
+pub mod plat_route;
+pub(crate) mod channel_session;
+///断线重连间隔
+const RECONNECT_INTERVAL: u64 = 3000;
+///在工作线程中重连通道直到成功,新连接通过 reconnect_callback 交给通道更新线程
+pub(crate) fn reconnect_async(name: &'static str, addr: String, reconnect_callback: crossbeam::channel::Sender<SyncSessionHandler<()>>){
+    shared::server::worker::spawn(async move {
+        loop {
+            match shared::server::channel::connect_async(addr.clone()).await {
+                Ok(handler) => {
+                    reconnect_callback.send(handler).map_err(|e| logthrow!(e,e)).ok();
+                    break;
+                },
+                Err(e) => {
+                    info!("{} {} reconnect fail!{:?}, try again after 3sec", name, addr, e);
+                    tokio::time::sleep(std::time::Duration::from_millis(RECONNECT_INTERVAL)).await;
+                }
+            }
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,219 @@
+//!平台服通讯通道
+//!
+//! 探索服主动连接平台服 channel_port 并注册服务器id、玩家连接地址及容量,之后随心跳上报负载
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use futures::FutureExt;
+use once_cell::sync::{Lazy, OnceCell};
+use shared::{SessionTransport, SyncSessionHandler, boxed::MutexArc, proto::PackBuffer, server::{channel::{self, ServiceChannel, ChannelState}}, timer::IntervalTimer};
+use super::channel_session::ExploreLoad;
+use crate::server::entry::ServerChannelEvent;
+///通道更新间隔
+const PLAT_CHANNEL_UPDATE_INTERVAL: u64 = 50;
+///平台服通道id起始值,与平台服连入的通道会话id区分
+pub const PLAT_CHANNEL_ID_BASE: usize = 1 << 48;
+///平台服序号 -> 通道
+static PLAT_CLIENTS: Lazy<RwLock<BTreeMap<usize, MutexArc<PlatChannel>>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///平台服消息交给 entry 处理
+static MSG_SENDER: OnceCell<tokio::sync::mpsc::UnboundedSender<(usize, PackBuffer)>> = OnceCell::new();
+pub struct PlatChannel{
+    handler: SyncSessionHandler<()>,
+    heart_timer: IntervalTimer,
+    state: ChannelState,
+    addr: String,
+    index: usize,
+    reconnect_handler: crossbeam::channel::Receiver<SyncSessionHandler<()>>,
+    reconnect_callback: crossbeam::channel::Sender<SyncSessionHandler<()>>,
+}
+impl PlatChannel{
+    ///连接平台服,直到连接成功
+    fn connect(addr: String, index: usize) -> Self{
+        let handler = loop {
+            if let Ok(conn) = channel::connect(addr.clone()){
+                break conn;
+            }
+            warn!("fail to connect to plat channel {}, try after 3 seconds",addr);
+            std::thread::sleep(std::time::Duration::from_millis(3000));
+        };
+        info!("plat_channel {} connect success,session id {}",addr,handler.id());
+        let mut channel = Self::new(handler, ChannelState::Connected);
+        channel.addr = addr;
+        channel.index = index;
+        channel
+    }
+    pub fn reconnect_async(&mut self) -> anyhow::Result<()>{
+        let addr = self.addr.clone();
+        super::reconnect_async("plat_channel", addr, self.reconnect_callback.clone());
+        self.state =  ChannelState::Reconnecting;
+        Ok(())
+    }
+    fn handle_reconnect(&mut self){
+        if let Ok(handler) = self.reconnect_handler.try_recv(){
+            self.handler = handler;
+            self.state =  ChannelState::Connected;
+            self.hand_shake().map_err(|e| logthrow!(e,"plat channel reconnect hand_shake fail",())).ok();
+            self.register();
+        }
+    }
+    #[inline]
+    fn reconnecting(&self) -> bool{
+        self.state == ChannelState::Reconnecting
+    }
+    ///发送本服负载
+    fn send_load(&self, msg_id: u16){
+        let msg = match ExploreLoad::current().to_value() {
+            Ok(msg) => msg,
+            Err(e) => return error!("fail to serialize explore load {:?}", e),
+        };
+        self.send_msg(SessionTransport::new(
+            shared::proto::proto_code::DEFAULT_MAIN_CODE,
+            msg_id,
+            0,
+            Box::new(msg)))
+        .map_err(|_| error!("fail to send load to plat server {}", self.addr)).ok();
+    }
+    ///向平台服注册
+    fn register(&self){
+        self.send_load(crate::msg_id::EXPLORE_REGISTER_REQ);
+    }
+}
+impl ServiceChannel<()> for PlatChannel{
+    #[inline]
+    fn handler_mut(&mut self) -> &mut SyncSessionHandler<()> {
+        &mut self.handler
+    }
+    #[inline]
+    fn handler(&self) -> &SyncSessionHandler<()> {
+        &self.handler
+    }
+
+    #[inline]
+    fn state(&self) -> channel::ChannelState {
+        self.state
+    }
+
+    fn new(handler: SyncSessionHandler<()>, state: ChannelState) -> Self {
+        let (tx,rx) = crossbeam::channel::bounded(1);
+        Self{
+            handler,
+            heart_timer: IntervalTimer::new(12*1000),
+            state,
+            addr: String::new(),
+            index: 0,
+            reconnect_handler: rx,
+            reconnect_callback: tx,
+        }
+    }
+    fn update(&mut self, diff: i64){
+        if self.reconnecting() {
+            self.handle_reconnect();
+            if self.reconnecting(){
+                return;
+            }
+        }
+        self.handle_event();
+        self.heart_timer.update(diff);
+        if self.heart_timer.passed(){
+            self.heart_timer.reset();
+            self.heartbeat().ok();
+            self.send_load(crate::msg_id::EXPLORE_LOAD_SYNC);
+        }
+    }
+
+    fn on_packet(&mut self, packet: PackBuffer)-> anyhow::Result<()> {
+        match packet.header().sub_code() as u16 {
+            crate::msg_id::EXPLORE_LOAD_REQ => self.send_load(crate::msg_id::EXPLORE_LOAD_SYNC),
+            crate::msg_id::PLAT_SERVER_SYNC => {
+                for msg in super::plat_route::on_identity(PLAT_CHANNEL_ID_BASE + self.index, &packet)? {
+                    self.send_msg(msg).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+                }
+            },
+            shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_REQ |
+            shared::proto::proto_code::msg_id_es_ps::FIGHT_SUCCESS_RESP |
+            crate::msg_id::ABANDON_EXPLORE_REQ => {
+                MSG_SENDER.get().expect("plat channel not started").send((PLAT_CHANNEL_ID_BASE + self.index, packet))
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+            },
+            opcode => {
+                warn!("unexpected opcode {} from plat channel {}",opcode,self.addr);
+            }
+        }
+        Ok(())
+    }
+    #[inline]
+    fn heart_timer(&mut self) -> &mut IntervalTimer {
+        &mut self.heart_timer
+    }
+
+    #[inline]
+    fn set_state(&mut self, s: ChannelState) {
+        self.state = s;
+    }
+    #[inline]
+    fn client_type(&self) -> shared::proto::ChannelClientType {shared::proto::ChannelClientType::ExploreServer }
+}
+///注册的平台服地址
+fn plat_addrs() -> Vec<String>{
+    shared::libconfig::config::get_str("plat_servers")
+    .map(|servers| servers.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+    .unwrap_or_default()
+}
+///启动平台服通道,每个平台服的连接与更新在独立线程中进行
+pub fn start_up() -> anyhow::Result<()>{
+    let addrs = plat_addrs();
+    if addrs.is_empty() {
+        info!("plat_servers not configured, waiting for plat servers to connect");
+        return Ok(());
+    }
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<(usize, PackBuffer)>();
+    MSG_SENDER.set(tx).map_err(|_| std::io::Error::from(std::io::ErrorKind::AlreadyExists))?;
+    shared::db::send_query(Box::new(async move {
+        while let Some((channel_id, packet)) = rx.recv().await {
+            crate::server::entry::on_channel_msg(ServerChannelEvent::ChannelMsg((channel_id, packet))).await
+            .map_err(|e| logthrow!(e,e)).ok();
+        }
+    }).boxed()).map_err(|e| logthrow!(e,std::io::Error::from(std::io::ErrorKind::BrokenPipe)))?;
+    for (index, addr) in addrs.into_iter().enumerate() {
+        std::thread::Builder::new().name(format!("plat_channel_{}", index)).spawn(move || {
+            let channel = PlatChannel::connect(addr, index);
+            channel.hand_shake().map_err(|e| logthrow!(e,"plat channel hand_shake fail",())).ok();
+            channel.register();
+            PLAT_CLIENTS.write().unwrap().insert(index, MutexArc::new(channel));
+            let mut last_time = shared::get_current_ms();
+            while !crate::server::start::stopped() {
+                let now = shared::get_current_ms();
+                update(index, (now - last_time).max(0));
+                last_time = now;
+                std::thread::sleep(std::time::Duration::from_millis(PLAT_CHANNEL_UPDATE_INTERVAL));
+            }
+        })?;
+    }
+    Ok(())
+}
+fn update(index: usize, diff: i64){
+    let clients = PLAT_CLIENTS.read().unwrap();
+    let mut plat_channel = clients.get(&index).unwrap().get_mut(None).unwrap();
+    plat_channel.update(diff);
+    if plat_channel.state() == ChannelState::Disconnected{
+        super::plat_route::unbind(PLAT_CHANNEL_ID_BASE + index);
+        plat_channel.reconnect_async().ok();
+    }
+}
+///是否为探索服主动连接的平台服通道
+#[inline]
+pub fn is_plat_channel(channel_id: usize) -> bool{
+    channel_id >= PLAT_CHANNEL_ID_BASE
+}
+///向平台服发送消息
+pub fn send_msg(msg: SessionTransport<()>, channel_id: usize) -> anyhow::Result<()>{
+    let clients = PLAT_CLIENTS.read().unwrap();
+    let channel = match clients.get(&(channel_id - PLAT_CHANNEL_ID_BASE)) {
+        Some(channel) => channel.get(),
+        None => return shared::error::any_err(std::io::ErrorKind::NotConnected),
+    };
+    if channel.state() != ChannelState::Connected {
+        return shared::error::any_err(std::io::ErrorKind::NotConnected);
+    }
+    channel.send_msg(msg).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//!平台服路由
+//!
+//! 平台服握手后上报固定的平台服id,探索按平台服id发送消息
+//! 平台服重连后通道id变化,暂时离线期间的消息缓存,重新绑定后补发
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use protobuf::well_known_types::StringValue;
+use shared::{SessionTransport, proto::PackBuffer};
+///平台服离线时每个平台服最多缓存的消息数量
+const PLAT_PENDING_LIMIT: usize = 4096;
+#[derive(Default)]
+struct PlatRoute{
+    ///当前通道id,离线时为None
+    channel: Option<usize>,
+    ///离线期间的消息
+    pending: VecDeque<SessionTransport<()>>,
+}
+///平台服id -> 路由
+static PLAT_ROUTES: Lazy<Mutex<HashMap<u32, PlatRoute>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+///平台服标识
+#[derive(Debug, serde::Deserialize)]
+struct PlatIdentity{
+    server_id: u32,
+}
+///解析平台服标识消息,绑定通道并返回离线期间缓存的消息
+pub fn on_identity(channel_id: usize, packet: &PackBuffer) -> anyhow::Result<Vec<SessionTransport<()>>>{
+    let pack = packet.unpack::<StringValue>().map_err(|_| shared::error::unpack_err())?;
+    let identity = serde_json::from_str::<PlatIdentity>(pack.get_value()).map_err(|e| logthrow!(e, e))?;
+    Ok(bind(identity.server_id, channel_id))
+}
+///绑定平台服与通道,返回离线期间缓存的消息
+pub fn bind(plat_server: u32, channel_id: usize) -> Vec<SessionTransport<()>>{
+    let mut routes = PLAT_ROUTES.lock().unwrap();
+    //同一通道只对应一个平台服
+    for (_, route) in routes.iter_mut().filter(|(id, route)| **id != plat_server && route.channel == Some(channel_id)) {
+        route.channel = None;
+    }
+    let route = routes.entry(plat_server).or_default();
+    route.channel = Some(channel_id);
+    let pending = route.pending.drain(..).collect::<Vec<_>>();
+    info!("plat server {} bound to channel {}, {} pending msgs", plat_server, channel_id, pending.len());
+    pending
+}
+///通道断开,对应平台服进入离线状态
+pub fn unbind(channel_id: usize){
+    let mut routes = PLAT_ROUTES.lock().unwrap();
+    for (plat_server, route) in routes.iter_mut().filter(|(_, route)| route.channel == Some(channel_id)) {
+        info!("plat server {} away from channel {}", plat_server, channel_id);
+        route.channel = None;
+    }
+}
+///通道对应的平台服id
+pub fn plat_server_of(channel_id: usize) -> Option<u32>{
+    PLAT_ROUTES.lock().unwrap().iter()
+    .find(|(_, route)| route.channel == Some(channel_id))
+    .map(|(plat_server, _)| *plat_server)
+}
+///请求来源的平台服id,平台服未上报标识时使用请求中的平台服id
+#[inline]
+pub fn resolve(channel_id: usize, claimed: u32) -> u32{
+    plat_server_of(channel_id).unwrap_or(claimed)
+}
+///向平台服发送消息,平台服离线时缓存
+pub fn send_msg(msg: SessionTransport<()>, plat_server: u32) -> anyhow::Result<()>{
+    let channel_id = {
+        let mut routes = PLAT_ROUTES.lock().unwrap();
+        let route = routes.entry(plat_server).or_default();
+        match route.channel {
+            Some(channel_id) => channel_id,
+            None => {
+                if route.pending.len() >= PLAT_PENDING_LIMIT {
+                    warn!("plat server {} away too long, drop oldest pending msg", plat_server);
+                    route.pending.pop_front();
+                }
+                route.pending.push_back(msg);
+                return Ok(());
+            }
+        }
+    };
+    super::channel_service::send_msg(msg, channel_id)
+}
+#[cfg(test)]
+#[test]
+fn test_plat_route(){
+    let plat_server = 10_001;
+    bind(plat_server, 1);
+    assert_eq!(plat_server_of(1), Some(plat_server));
+    unbind(1);
+    assert_eq!(plat_server_of(1), None);
+    send_msg(SessionTransport::disconnect(), plat_server).unwrap();
+    send_msg(SessionTransport::disconnect(), plat_server).unwrap();
+    //重连后通道id变化,缓存的消息交给新通道
+    assert_eq!(bind(plat_server, 2).len(), 2);
+    assert_eq!(plat_server_of(2), Some(plat_server));
+    assert!(bind(plat_server, 2).is_empty());
+}
@@ -5,9 +5,9 @@ use crate::server::explore::{player_session::PlayerSessionBuilder};
 use super::explore::{player_session::PlayerSessionInfo, ExploreSharedChannel};
 use once_cell::sync::Lazy;
 use shared::{AsyncSessionHandler, SessionTransport, SocketMessage, proto::PackBuffer};
-use shared::boxed::MutexArc;
 use super::explore::{Explore};
-use std::collections::BTreeMap;
+use super::explore::command::{ExploreCommand, ExploreSnapshot};
+use super::registry::ShardedRegistry;
 ///服务channel消息
 #[derive(Debug)]
 pub enum ServerChannelEvent{
@@ -17,9 +17,13 @@ pub enum ServerChannelEvent{
 pub struct ExploreHandler{
     pub player_id: u64,
     pub handler: tokio::sync::mpsc::UnboundedSender<SocketMessage<PlayerSessionInfo>>,
+    ///后台命令
+    pub command: tokio::sync::mpsc::UnboundedSender<ExploreCommand>,
 }
-///channel map
-static CHANNEL_MAP: Lazy<MutexArc<BTreeMap<u64,ExploreHandler>>> = Lazy::new(|| MutexArc::new(BTreeMap::new()));
+///后台命令等待超时
+const COMMAND_TIMEOUT: u64 = 3*1000;
+///进行中的探索
+static CHANNEL_MAP: Lazy<ShardedRegistry<ExploreHandler>> = Lazy::new(ShardedRegistry::new);
 pub fn on_new_session(session: AsyncSessionHandler<ExploreSharedChannel>){
     let id = session.id();
     trace!("on_new_session {}", id);
@@ -37,14 +41,13 @@ pub async fn on_channel_msg(msg: ServerChannelEvent)->anyhow::Result<()>{
 }
 pub fn remove_explore(player_id: u64){
     info!("explore of player {} closed",player_id);
-    let mut channels = CHANNEL_MAP.get_mut(None).unwrap();
-    channels.remove(&player_id);
+    CHANNEL_MAP.remove(player_id);
 }
 pub(crate) async fn bind_explore(player_id: u64, player_info: PlayerSessionInfo) -> anyhow::Result<()>{
-    let event_handlers = CHANNEL_MAP.get();
-    match event_handlers.get(&player_id){
+    let handler = CHANNEL_MAP.attach(player_id, |explore| explore.handler.clone());
+    match handler{
         Some(handler) => {
-            handler.handler.send(SocketMessage::Template(player_info))?;
+            handler.send(SocketMessage::Template(player_info))?;
         },
         None => {
             //没有这个探索,直接断开连接
@@ -62,27 +65,43 @@ async fn handle_channel_msg(channel_id: usize, packet: PackBuffer)-> anyhow::Res
                 explore_id, 
                 characters,
                 gm_authority,
-                tasks_id,..
+                tasks_id,
+                plat_server_id,..
             } = packet.unpack::<shared::proto::Ps2EsMsgExploreReq>().map_err(|_| shared::error::send_err())?;
             {
-                if let Some(explore) =  CHANNEL_MAP.get().get(&player_id){
+                if let Some(handler) =  CHANNEL_MAP.with(player_id, |explore| explore.handler.clone()){
                     info!("explore player {} exist, try connecting...",player_id);
-                    explore.handler.send(SocketMessage::ChannelMessage((channel_id,packet)))
+                    handler.send(SocketMessage::ChannelMessage((channel_id,packet)))
                     .map_err(|e| logthrow!(e,"fail to send channel msg",shared::error::send_err()))?;
                     return Ok(());
                 }
             }
             log_info!("create explore chapter {}, player {}, tasks {:?}", explore_id, player_id, tasks_id);
+            let plat_server = super::channel::plat_route::resolve(channel_id, plat_server_id as u32);
             let header = packet.header();
             //创建探索,并加载数据
-            let mut explore = Explore::create(
+            let mut explore = match Explore::create(
                 player_id, 
                 explore_id, 
-                channel_id, 
+                plat_server, 
                 characters, 
                 gm_authority,
                 tasks_id.first().copied().unwrap_or_default().max(0)
-            )?;
+            ){
+                Ok(explore) => explore,
+                Err(e) => {
+                    error!("create explore {:?} fail {:?}",(player_id,explore_id),e);
+                    let mut resp = shared::proto::Es2PsMsgExploreResp::new();
+                    resp.set_result(shared::proto::ExploreCreateResult::FAIL);
+                    resp.set_player_id(player_id);
+                    super::channel::plat_route::send_msg(SessionTransport::new(
+                        shared::proto::proto_code::DEFAULT_MAIN_CODE,
+                        shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_RESP,
+                        header.squence(), 
+                          Box::new(resp)), plat_server)?;
+                    return Ok(());
+                }
+            };
             match explore.create_explore(Some(1*60*1000)).await{
                 Ok(_) => {
                     let mut resp = shared::proto::Es2PsMsgExploreResp::new();
@@ -90,7 +109,7 @@ async fn handle_channel_msg(channel_id: usize, packet: PackBuffer)-> anyhow::Res
                     resp.set_explore_uuid(explore.get_explore_uuid());
                     resp.set_player_id(player_id);
                     resp.set_access_token(explore.access_token().to_string());
-                    super::channel::channel_service::send_msg(SessionTransport::new(
+                    super::channel::plat_route::send_msg(SessionTransport::new(
                         shared::proto::proto_code::DEFAULT_MAIN_CODE,
                         shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_RESP, 
                         header.squence(), 
@@ -101,16 +120,20 @@ async fn handle_channel_msg(channel_id: usize, packet: PackBuffer)-> anyhow::Res
                     error!("create explore {:?} fail {:?}",(player_id,explore_id),e);
                     let mut resp = shared::proto::Es2PsMsgExploreResp::new();
                     resp.set_result(shared::proto::ExploreCreateResult::FAIL);
-                    super::channel::channel_service::send_msg(SessionTransport::new(
+                    resp.set_player_id(player_id);
+                    super::channel::plat_route::send_msg(SessionTransport::new(
                         shared::proto::proto_code::DEFAULT_MAIN_CODE,
                         shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_RESP,
                         header.squence(), 
-                          Box::new(resp)), channel_id)?;
+                          Box::new(resp)), plat_server)?;
+                    return Ok(());
                 }
             }
+            let explore_uuid = explore.get_explore_uuid();
             let mut context = shared::AsyncContext::<Explore,_, ()>::from(None, explore);
             let event_handler = context.inner_mut().event_handler();
-            CHANNEL_MAP.get_mut(None).unwrap().insert(player_id, ExploreHandler{handler: event_handler, player_id});
+            let command = context.inner_mut().command_handler();
+            CHANNEL_MAP.insert(player_id, explore_uuid, ExploreHandler{handler: event_handler, command, player_id});
             context.start().map_err(|e| error!("fail to  start context{}, {:?}",player_id,e)).ok();
 
         },
@@ -119,20 +142,84 @@ async fn handle_channel_msg(channel_id: usize, packet: PackBuffer)-> anyhow::Res
                 player_id,..
             } = packet.unpack::<shared::proto::Ps2EsMsgFightSucessResp>().map_err(|_| shared::error::send_err())?;
             {
-                if let Some(explore) =  CHANNEL_MAP.get().get(&player_id){
-                    explore.handler.send(SocketMessage::ChannelMessage((channel_id,packet)))
+                if let Some(handler) =  CHANNEL_MAP.with(player_id, |explore| explore.handler.clone()){
+                    handler.send(SocketMessage::ChannelMessage((channel_id,packet)))
                     .map_err(|e| logthrow!(e,"fail to send channel msg",shared::error::send_err()))?;
                     return Ok(());
                 }
             }
         },
+        crate::msg_id::ABANDON_EXPLORE_REQ => {
+            let shared::proto::Ps2EsMsgExploreReq{
+                player_id,
+                plat_server_id,..
+            } = packet.unpack::<shared::proto::Ps2EsMsgExploreReq>().map_err(|_| shared::error::send_err())?;
+            {
+                if let Some(handler) =  CHANNEL_MAP.with(player_id, |explore| explore.handler.clone()){
+                    handler.send(SocketMessage::ChannelMessage((channel_id,packet)))
+                    .map_err(|e| logthrow!(e,"fail to send channel msg",shared::error::send_err()))?;
+                    return Ok(());
+                }
+            }
+            //探索未加载,直接标记为已放弃
+            let explores = super::explore::db_handler::DbHandler::on_abandon_explore(player_id).await?;
+            info!("player {} abandon unloaded explores {:?}", player_id, explores);
+            let plat_server = super::channel::plat_route::resolve(channel_id, plat_server_id as u32);
+            for explore_uuid in explores {
+                let sync = super::channel::channel_session::ExploreSettleSync{ player_id, explore_uuid, result: shared::proto::ExploreResult::FAILED as i32 };
+                super::channel::plat_route::send_msg(sync.to_msg()?, plat_server)?;
+            }
+        },
         opcode => {
             error!("unrecognized channel opcode {}",opcode);
         },
     }
     Ok(())
 }
-#[allow(unused)]
 pub(crate) fn get_bearing() -> usize {
-    CHANNEL_MAP.get_mut(None).unwrap().len()
+    CHANNEL_MAP.len()
+}
+///进行中的探索玩家列表
+pub(crate) fn explore_players() -> Vec<u64>{
+    CHANNEL_MAP.keys()
+}
+///根据探索uuid查找玩家id
+pub(crate) fn explore_player_by_uuid(uuid: u64) -> Option<u64>{
+    CHANNEL_MAP.player_of(uuid)
+}
+///订阅探索生命周期事件
+#[allow(unused)]
+pub(crate) fn subscribe_explores() -> tokio::sync::broadcast::Receiver<super::registry::RegistryEvent>{
+    CHANNEL_MAP.subscribe()
+}
+///战斗服返回的战斗结果,转交给所属探索
+pub(crate) fn on_battle_result(result: super::explore::battle::BattleResult){
+    let player_id = result.player_id;
+    match CHANNEL_MAP.with(player_id, |explore| explore.command.clone()) {
+        Some(sender) => {
+            sender.send(ExploreCommand::BattleResult(result)).map_err(|e| error!("fail to send battle result to explore {}, {:?}", player_id, e)).ok();
+        },
+        None => warn!("battle result of player {} dropped, explore not found", player_id),
+    }
+}
+///发送后台命令并等待结果,探索不存在或超时返回None
+async fn send_command<T>(player_id: u64, cmd: impl FnOnce(tokio::sync::oneshot::Sender<T>) -> ExploreCommand) -> Option<T>{
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let sender = CHANNEL_MAP.with(player_id, |explore| explore.command.clone())?;
+    sender.send(cmd(tx)).map_err(|e| error!("fail to send command to explore {}, {:?}", player_id, e)).ok()?;
+    match tokio::time::timeout(std::time::Duration::from_millis(COMMAND_TIMEOUT), rx).await{
+        Ok(result) => result.ok(),
+        Err(_) => {
+            warn!("explore command of player {} timeout", player_id);
+            None
+        }
+    }
+}
+///获取探索快照
+pub(crate) async fn explore_snapshot(player_id: u64) -> Option<ExploreSnapshot>{
+    send_command(player_id, ExploreCommand::Snapshot).await
+}
+///强制关闭探索
+pub(crate) async fn close_explore(player_id: u64) -> bool{
+    send_command(player_id, ExploreCommand::Close).await.unwrap_or_default()
 }
\ No newline at end of file
@@ -0,0 +1,18 @@
+//! 探索战斗模拟
+//!
+//! 消息结构及模拟规则在 battle_proto 中与战斗服共用,客户端使用同样的种子和规则计算结果,服务器以模拟结果为准
+//! 配置了战斗服时,战斗交由战斗服计算,战斗服不可用时在本地模拟
+use shared::attribute::EAttributeType;
+use super::explore_player::ExploreCharacter;
+pub use battle_proto::{BattleCharacterResult, BattleOutcome, BattleRequest, BattleResult, BattleUnit, lost, simulate};
+impl From<&ExploreCharacter> for BattleUnit{
+    fn from(c: &ExploreCharacter) -> Self {
+        Self{
+            id: c.config_id,
+            attack: c.get_attr(EAttributeType::Attack),
+            defense: c.get_attr(EAttributeType::Defense),
+            health: c.get_attr(EAttributeType::Health),
+            max_health: c.get_attr(EAttributeType::MaxHealth),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! 探索后台命令
+//!
+//! 后台接口通过命令通道访问运行中的探索,结果由oneshot返回,战斗服结果也经由命令通道送回探索
+use serde::Serialize;
+use shared::map::Point2;
+use tokio::sync::oneshot;
+use super::db_handler::ExploreCharacterInfo;
+///后台命令
+#[derive(Debug)]
+pub enum ExploreCommand{
+    ///获取探索快照
+    Snapshot(oneshot::Sender<ExploreSnapshot>),
+    ///强制关闭探索,关闭前会保存探索
+    Close(oneshot::Sender<bool>),
+    ///战斗服返回的战斗结果
+    BattleResult(super::battle::BattleResult),
+}
+///探索快照
+#[derive(Debug, Clone, Serialize)]
+pub struct ExploreSnapshot{
+    pub explore_uuid: u64,
+    pub player_id: u64,
+    pub explore_id: u32,
+    pub state: String,
+    pub plat_server: u32,
+    pub player_session: usize,
+    pub food: i32,
+    pub position: Point2,
+    pub origin_position: Point2,
+    pub step: u32,
+    pub characters: Vec<ExploreCharacterInfo>,
+    ///当前进行中的事件id
+    pub current_event: Option<u32>,
+    ///已完成事件id
+    pub finished_events: Vec<u32>,
+    pub variables: Vec<(i32, i32)>,
+    ///已探索格子数量
+    pub explored_tiles: usize,
+    pub battle_seed: i32,
+    ///任务id
+    pub task_id: u32,
+    ///任务目标进度(当前,目标)
+    pub objectives: Vec<(u32, u32)>,
+}
+///探索列表项
+#[derive(Debug, Clone, Serialize)]
+pub struct ExploreBrief{
+    pub player_id: u64,
+    pub explore_uuid: u64,
+    pub state: String,
+    pub food: i32,
+    pub position: Point2,
+}
+impl From<&ExploreSnapshot> for ExploreBrief{
+    fn from(s: &ExploreSnapshot) -> Self {
+        Self{
+            player_id: s.player_id,
+            explore_uuid: s.explore_uuid,
+            state: s.state.clone(),
+            food: s.food,
+            position: s.position,
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use shared::map::Point2;
-use sqlx::{Row, mysql::MySqlRow};
 use futures::FutureExt;
 #[derive(Debug, sqlx::FromRow, Clone)]
 pub struct ExploreEventInfo{
@@ -10,6 +9,24 @@ pub struct ExploreEventInfo{
     pub position: sqlx::types::Json<Point2>,
     pub progress_event: i32,
 }
+///探索中角色状态
+#[derive(Debug, sqlx::FromRow, Clone, serde::Serialize)]
+pub struct ExploreCharacterInfo{
+    pub role_id: u32,
+    pub health: i32,
+    pub max_health: i32,
+    pub state: i32,
+}
+impl From<&super::explore_player::ExploreCharacter> for ExploreCharacterInfo{
+    fn from(c: &super::explore_player::ExploreCharacter) -> Self {
+        Self{
+            role_id: c.config_id,
+            health: c.health() as i32,
+            max_health: c.max_health() as i32,
+            state: c.state as i32,
+        }
+    }
+}
 impl From<&super::explore_event::ExploreEvent> for ExploreEventInfo{
     fn from(evt: &super::explore_event::ExploreEvent) -> Self {
         Self{
@@ -28,7 +45,7 @@ pub const EXPLORE_STATE_NORMAL: i32 = 0;
 pub const EXPLORE_STATE_FINISHED: i32 = 1;
 ///探索取消状态
 pub const EXPLORE_STATE_REMOVED: i32 = 2;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExploreInfo{
     pub id: u64,
     pub player_id: u64,
@@ -44,6 +61,12 @@ pub struct ExploreInfo{
     pub food: i32,
     ///已完成事件
     pub unique_events: Vec<(i32,u32)>,
+    ///出生点
+    pub origin_position: Option<sqlx::types::Json<Point2>>,
+    ///已探索区域,按格子id存储的位图
+    pub explored_map: Vec<u8>,
+    ///队伍角色状态
+    pub characters: Vec<ExploreCharacterInfo>,
 }
 impl Default for ExploreInfo{    
     fn default() -> Self {
@@ -61,32 +84,43 @@ impl Default for ExploreInfo{
             position: Default::default(),
             food: 0,
             unique_events: Default::default(),
+            origin_position: Default::default(),
+            explored_map: Default::default(),
+            characters: Default::default(),
         }
      }
 }
-impl<'a> sqlx::FromRow<'a, MySqlRow> for ExploreInfo{
-    fn from_row(row: &'a MySqlRow) -> Result<Self, sqlx::Error> {
-        Ok(Self{
-            id: row.try_get("id").unwrap_or_default(),
-            player_id: row.try_get("player_id").unwrap_or_default(),
-            state: row.try_get("state").unwrap_or_default(),
-            explore_id: row.try_get("explore_id").unwrap_or_default(),
-            max_event: row.try_get("max_event").unwrap_or_default(),
-            finished_event: row.try_get("finished_event").unwrap_or_default(),
-            create_time: row.try_get("create_time").unwrap_or(chrono::Local::now()),
-            token: row.try_get("token").unwrap_or_default(),
-            position: row.try_get("position").unwrap_or_default(),
-            food: row.try_get("food").unwrap_or_default(),
-            finished_events: Default::default(),
-            unique_events: Default::default(),
-            variables: Default::default(),
-        })
-    }
-}
 impl ExploreInfo{
+    ///新建的探索
+    pub fn new(player_id: u64, explore_id: u32, food: i32, base_point: Point2) -> Self{
+        let mut explore = Self::default();
+        explore.player_id = player_id;
+        explore.explore_id = explore_id;
+        explore.food = food;
+        explore.position = Some(sqlx::types::Json(base_point));
+        explore.origin_position = Some(sqlx::types::Json(base_point));
+        explore
+    }
     pub fn get_events(&self) -> &Vec<ExploreEventInfo>{
         &self.finished_events
     }
+    ///已探索的格子id
+    pub fn explored_points(&self) -> Vec<u16>{
+        self.explored_map.iter().enumerate().flat_map(|(index, bits)|{
+            (0..8u16).filter(move |bit| bits & (1 << bit) != 0).map(move |bit| index as u16 * 8 + bit)
+        }).collect()
+    }
+    ///设置已探索的格子id
+    pub fn set_explored_points(&mut self, points: impl Iterator<Item = u16>){
+        self.explored_map.clear();
+        for id in points {
+            let index = id as usize / 8;
+            if self.explored_map.len() <= index{
+                self.explored_map.resize(index + 1, 0);
+            }
+            self.explored_map[index] |= 1 << (id % 8);
+        }
+    }
 }
 #[derive(Default, Debug)]
 pub struct DbHandler{
@@ -98,112 +132,66 @@ impl DbHandler{
         //暂时关闭探索保存功能
         shared::db::send_query(Box::new(async move {
             DbHandler::on_save_explore(&explore).await
-            .map_err(|e| error!("fail {:?} to save explore info {:?}",e,explore)).ok();
+            .map_err(|e| {
+                crate::server::metrics::inc_save_failure();
+                error!("fail {:?} to save explore info {:?}",e,explore)
+            }).ok();
         }).boxed())?;
         Ok(())
     }
-    ///load player, if not exist, create player
+    ///保存探索快照
     async fn on_save_explore(explore: &ExploreInfo) -> anyhow::Result<()>{
-        let pool = shared::db::get_pool("db_explore")?;
-        let _time = if_else!(explore.state == EXPLORE_STATE_FINISHED,Some(chrono::Local::now()),None);
-        //暂不保存探索信息
-        let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
-         sqlx::query("UPDATE db_explore SET finished_event=? WHERE id=?")
-        .bind(explore.finished_events.len() as i32)
-        .bind(explore.id)
-        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
-        for event in explore.get_events() {
-            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_finished_event WHERE player_id=? AND event_type=? AND event_id=?")
-            .bind(explore.player_id).bind(event.event_type).bind(event.event_id)
-            .fetch_one(&mut trans).await.map_err(|e| logthrow!(e,e))?.0;
-            if count > 0{
-                continue;
-            }
-            sqlx::query("INSERT INTO db_finished_event 
-            (player_id,scene_type,scene_id,event_id,event_type,`position`,progress_event) 
-            VALUES(?,0,?,?,?,?,?,?,?)")
-            .bind(explore.player_id)
-            .bind(explore.explore_id)
-            .bind(event.event_id)
-            .bind(event.event_type)
-            .bind(serde_json::to_string(&event.position.0)?)
-            .bind(event.progress_event)
-            .execute(&mut trans).await.map_err(|e| logthrow!(e,e)).ok();
-        }
-        let player_id = explore.player_id;
-        let explore_id = explore.explore_id;
-        let variable_list = explore.variables.iter().map(|t| format!("({},0,{},{},{})",player_id,explore_id,t.0,t.1)).collect::<Vec<_>>();
-        if variable_list.len() > 0{
-            let variables = format!("REPLACE INTO global_explore_variables (player_id,scene_type,scene_id,variable_type,value) VALUES {}",
-            variable_list.join(","));
-            sqlx::query(&variables)
-           .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
-        }
-        trans.commit().await.map_err(|e| logthrow!(e,e))?;
-        Ok(())
+        let start = std::time::Instant::now();
+        let ret = super::storage::get().on_save_explore(explore).await;
+        crate::server::metrics::observe_db("save_explore", start.elapsed());
+        ret
     }
-    ///加载或者创建探索
-    pub async fn on_create_explore(player_id: u64, explore_id: u32, token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>{
-        info!("load explore {:?} ", (player_id, explore_id, token));
-        let pool = shared::db::get_pool("db_explore")?;
-        let current = sqlx::query_as::<_,ExploreInfo>("SELECT * FROM db_explore WHERE player_id=? AND state=0")
-        .bind(&player_id)
-        .fetch_optional(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-        let mut explore = if current.is_some(){
-            info!("explore loaded {:?}",current);
-            let mut current = current.unwrap();
-            let events = sqlx::query_as::<_,ExploreEventInfo>("SELECT * FROM db_finished_event WHERE player_id=? AND progress_event=1 AND scene_type=0 AND scene_id=?")
-            .bind(player_id).bind(current.explore_id)
-            .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-            current.finished_events = events;
-            current.variables = sqlx::query_as("SELECT variable_type,`value` FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=?")
-            .bind(player_id).bind(current.explore_id)
-            .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-            current
-        }
-        else{
-            let food = shared::libconfig::common::get_value("DefaultFood").unwrap_or(100);
-            let query = sqlx::query("INSERT INTO db_explore (player_id,explore_id,token,food,`position`) VALUES(?,?,?,?,?)")
-            .bind(player_id).bind(explore_id).bind(token).bind(food).bind(serde_json::to_string(&base_point)?)
-            .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-            info!("insert new explore {}", query.last_insert_id());
-            let mut explore = ExploreInfo::default();
-            explore.id = query.last_insert_id();
-            explore.player_id = player_id;
-            explore.explore_id = explore_id;
-            explore.food = food;
-            explore.position = Some(sqlx::types::Json(base_point));
-            explore
-        };
-        explore.unique_events = sqlx::query_as("SELECT event_type,event_id FROM db_unique_event WHERE player_id=?")
-        .bind(player_id)
-        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+    ///加载或者创建探索,没有进行中的探索时以uuid新建
+    pub async fn on_create_explore(player_id: u64, uuid: u64, explore_id: u32, token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>{
+        info!("load explore {:?} ", (player_id, uuid, explore_id, token));
+        let start = std::time::Instant::now();
+        let explore = super::storage::get().on_create_explore(player_id, uuid, explore_id, token, base_point).await;
+        crate::server::metrics::observe_db("create_explore", start.elapsed());
+        let explore = explore?;
         info!("on_create_explore {:?}", explore);
         Ok(explore)
     }
     ///保存已入队角色
     pub async fn save_character(player_id: u64, charactes: &Vec<u32>) -> anyhow::Result<()>{
-        let pool = shared::db::get_pool("db_explore")?;
-        for cid in charactes {
-            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_player_character WHERE player_id=? AND role_id=?")
-            .bind(player_id).bind(cid)
-            .fetch_one(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?.0;
-            if count > 0{
-                continue;
-            }
-            sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,1,1)")
-            .bind(cid).bind(player_id)
-            .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-        }
+        let start = std::time::Instant::now();
+        let ret = super::storage::get().save_character(player_id, charactes).await;
+        crate::server::metrics::observe_db("save_character", start.elapsed());
+        ret?;
         info!("player explore {} save_character {:?}", player_id, charactes);
         Ok(())
     }
     ///quit explore ,ignore error event at present
     pub async fn on_quit_explore(explore_id: u64) -> anyhow::Result<()>{
-        let pool = shared::db::get_pool("db_explore")?;
-        sqlx::query("UPDATE db_explore SET state=2 WHERE id=?")
-        .bind(explore_id)
-        .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-        Ok(())
+        let start = std::time::Instant::now();
+        let ret = super::storage::get().on_quit_explore(explore_id).await;
+        crate::server::metrics::observe_db("quit_explore", start.elapsed());
+        ret
     }
-}
\ No newline at end of file
+    ///放弃玩家未加载的探索
+    pub async fn on_abandon_explore(player_id: u64) -> anyhow::Result<Vec<u64>>{
+        let start = std::time::Instant::now();
+        let ret = super::storage::get().on_abandon_explore(player_id).await;
+        crate::server::metrics::observe_db("abandon_explore", start.elapsed());
+        ret
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_explored_points(){
+    let mut info = ExploreInfo::default();
+    info.set_explored_points(std::iter::empty());
+    assert!(info.explored_map.is_empty());
+    assert!(info.explored_points().is_empty());
+    let points = vec![0u16, 1, 7, 8, 15, 16, 1023, u16::MAX];
+    info.set_explored_points(points.iter().copied());
+    assert_eq!(info.explored_map.len(), u16::MAX as usize / 8 + 1);
+    assert_eq!(info.explored_points(), points);
+    //重复设置时覆盖旧数据
+    info.set_explored_points(vec![3u16, 3].into_iter());
+    assert_eq!(info.explored_points(), vec![3]);
+}
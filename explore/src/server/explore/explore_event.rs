@@ -38,6 +38,12 @@ pub struct ExploreEvent{
     pub notify_state: bool,    
     pub position: Point2,
     pub progress_event: bool,
+    ///触发范围
+    pub trigger_range: u32,
+    ///战斗敌人id,非战斗事件为0
+    pub enemy_id: u32,
+    ///完成获得的物品(物品id,数量)
+    pub item: Option<(u32, u32)>,
 }
 impl PartialEq for ExploreEvent{
     fn eq(&self, other: &Self) -> bool {
@@ -45,6 +51,11 @@ impl PartialEq for ExploreEvent{
     }
 }
 impl ExploreEvent{
+    ///是否战斗事件
+    #[inline]
+    pub fn is_battle(&self) -> bool{
+        self.enemy_id > 0
+    }
     pub fn new(
         id: u64, 
         map_id: u32,
@@ -64,6 +75,9 @@ impl ExploreEvent{
             notify_state: state != GameEventState::Unfinished,
             position,
             progress_event,
+            trigger_range: 0,
+            enemy_id: 0,
+            item: None,
         }
     }
 }
@@ -5,7 +5,7 @@ use std::ops::BitAnd;
 use shared::{AsyncSessionHandler, SessionTransport};
 use shared::{proto::EExploreEventType};
 use shared::attribute::{AttributeBinder, EAttributeType};
-use shared::map::Point2;
+use shared::map::{Map, Point2};
 use chrono::prelude::*;
 use super::{ExploreSharedChannel};
 use super::explore_event::{ExploreEvent, GameEventState, EventInfo};
@@ -147,6 +147,15 @@ impl ExplorePlayer{
     pub fn set_dirty(&mut self, flag: u32){
         self.dirty_flag |= flag;
     }
+    ///恢复保存的角色状态
+    pub fn restore_character(&mut self, character: u32, health: i32, max_health: i32, state: CharacterState){
+        if let Some(cha) = self.characters.iter_mut().find(|c| c.config_id == character){
+            cha.set_base_attr(EAttributeType::MaxHealth, max_health);
+            cha.set_base_attr(EAttributeType::Health, health);
+            cha.state = state;
+            self.dirty_flag |= explore_player_dirty_flag::ATTRIBUTE;
+        }
+    }
     pub fn exp(&self, character: u32) -> i32 {
         self.characters.iter().find(|c| c.config_id == character).map(|c| c.get_exp()).unwrap_or_default()
     }
@@ -154,18 +163,8 @@ impl ExplorePlayer{
         self.characters.iter_mut().find(|c| c.config_id == character).unwrap().add_exp(exp);
     }
 }
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CharacterState{
-    ///活跃
-    Active = 0,
-    ///重伤
-    Injured = 1,
-    ///不可用
-    Unusable = 2,
-}
-impl Default for CharacterState{    
-    fn default() -> Self { CharacterState::Active }
-}
+///角色状态,与战斗服共用
+pub use battle_proto::CharacterState;
 #[derive(Debug,Clone,Default)]
 pub struct ExploreCharacter{
     pub config_id: u32,
@@ -259,6 +258,27 @@ impl ExplorePlayer{
             self.visiable_points_local.push(pos);   //加入位置坐标
         }
     }
+    ///恢复已探索的区域,不作为新揭开的点下发
+    pub fn restore_visiable_points(&mut self, map: &Map, explored: &[u16]){
+        for point in map.iter() {
+            let point: Point2 = point.clone();
+            if explored.contains(&(point.id() as u16)) && !self.visiable_points_local.contains(&point){
+                self.visiable_points_local.push(point);
+            }
+        }
+    }
+    ///揭开当前位置视野范围内的迷雾,新揭开的点存入visiable_points,返回新揭开的数量
+    pub fn reveal(&mut self, map: &Map, hex: bool) -> usize{
+        let count = self.visiable_points.len();
+        let (position, fov) = (self.position, self.fov);
+        for point in map.iter() {
+            let point: Point2 = point.clone();
+            if super::get_distance(position, point, hex) <= fov{
+                self.add_visiable_point(point);
+            }
+        }
+        self.visiable_points.len() - count
+    }
     ///切换暗雷触发器
     #[inline]
     fn switch_trigger(&mut self, enabled: bool) {
@@ -1,23 +1,27 @@
 mod explore;
 mod explore_player;
 mod explore_event;
-mod db_handler;
+pub mod db_handler;
 mod trigger;
-use std::sync::atomic::{Ordering, AtomicU64};
+pub mod battle;
+mod objective;
+pub mod command;
+pub(crate) mod token;
+mod snowflake;
+use shared::map::Point2;
 pub use explore::*;
 pub mod player_session;
-///探索id
-static EXPLORE_ID:AtomicU64 =  AtomicU64::new(1);
-///获取探索id
-pub(crate) fn get_uuid(server_id: u32) -> u64{
-    let flag = (server_id as u64) << 48u64;
-    let ms = shared::get_current_ms();
-    let mut explore_id = ms.abs() as u64 | flag;
-    while EXPLORE_ID.load(Ordering::Acquire) >= explore_id{
-        std::thread::sleep(std::time::Duration::from_millis(1));
-        let ms = shared::get_current_ms();
-        explore_id = ms.abs() as u64 | flag;
+pub mod storage;
+pub(crate) use snowflake::get_uuid;
+
+///计算两点间的格子距离,hex为六边形地图(轴坐标)
+pub(crate) fn get_distance(from: Point2, to: Point2, hex: bool) -> u32{
+    let dx = from.x as i64 - to.x as i64;
+    let dy = from.y as i64 - to.y as i64;
+    if hex {
+        ((dx.abs() + dy.abs() + (dx + dy).abs()) / 2) as u32
+    }
+    else{
+        dx.abs().max(dy.abs()) as u32
     }
-    EXPLORE_ID.store(explore_id, Ordering::Release);
-    explore_id
 }
@@ -0,0 +1,103 @@
+//! 探索任务目标
+//!
+//! 目标进度保存在探索变量中,变量类型为 OBJECTIVE_VARIABLE_BASE + 目标序号,随探索快照一起保存
+use shared::map::Point2;
+use crate::config::explore_task::ObjectiveConfig;
+use super::explore_event::ExploreEvent;
+///目标进度变量起始类型
+pub const OBJECTIVE_VARIABLE_BASE: i32 = 10000;
+#[derive(Debug, Default)]
+pub struct ExploreObjectives{
+    task_id: u32,
+    objectives: Vec<ObjectiveConfig>,
+}
+impl ExploreObjectives{
+    ///加载任务目标,task_id为0时没有目标
+    pub fn new(task_id: u32, explore_id: u32) -> anyhow::Result<Self>{
+        if task_id == 0{
+            return Ok(Self::default());
+        }
+        match crate::config::explore_task::get_task(task_id) {
+            Some(task) if task.explore_id == 0 || task.explore_id == explore_id => Ok(Self{ task_id, objectives: task.objectives }),
+            Some(task) => {
+                error!("explore task {} belongs to explore {}, not {}", task_id, task.explore_id, explore_id);
+                shared::error::any_err(std::io::ErrorKind::InvalidInput)
+            },
+            None => {
+                error!("explore task {} config not found", task_id);
+                shared::error::any_err(std::io::ErrorKind::NotFound)
+            }
+        }
+    }
+    #[inline]
+    pub fn task_id(&self) -> u32{ self.task_id }
+    #[inline]
+    pub fn is_empty(&self) -> bool{ self.objectives.is_empty() }
+    fn get(variables: &[(i32, i32)], index: usize) -> u32{
+        let key = OBJECTIVE_VARIABLE_BASE + index as i32;
+        variables.iter().find(|(k, _)| *k == key).map(|(_, v)| (*v).max(0) as u32).unwrap_or_default()
+    }
+    fn add(&self, variables: &mut Vec<(i32, i32)>, index: usize, value: u32){
+        let key = OBJECTIVE_VARIABLE_BASE + index as i32;
+        let progress = (Self::get(variables, index) + value).min(self.objectives[index].target()) as i32;
+        match variables.iter_mut().find(|(k, _)| *k == key) {
+            Some(v) => v.1 = progress,
+            None => variables.push((key, progress)),
+        }
+    }
+    ///玩家到达格子
+    pub fn on_position(&self, position: Point2, variables: &mut Vec<(i32, i32)>){
+        for (index, objective) in self.objectives.iter().enumerate() {
+            if let ObjectiveConfig::ReachTile{ x, y } = objective {
+                if position.x == *x && position.y == *y{
+                    self.add(variables, index, 1);
+                }
+            }
+        }
+    }
+    ///事件完成
+    pub fn on_event_finished(&self, event: &ExploreEvent, variables: &mut Vec<(i32, i32)>){
+        for (index, objective) in self.objectives.iter().enumerate() {
+            let value = match objective {
+                ObjectiveConfig::ProgressEvents{..} if event.progress_event => 1,
+                ObjectiveConfig::DefeatEnemy{ enemy_id, .. } if event.enemy_id == *enemy_id => 1,
+                ObjectiveConfig::CollectItem{ item_id, .. } => event.item.filter(|(id, _)| id == item_id).map(|(_, count)| count).unwrap_or_default(),
+                _ => 0,
+            };
+            if value > 0{
+                self.add(variables, index, value);
+            }
+        }
+    }
+    ///目标进度(当前,目标)
+    pub fn progress(&self, variables: &[(i32, i32)]) -> Vec<(u32, u32)>{
+        self.objectives.iter().enumerate().map(|(index, o)| (Self::get(variables, index), o.target())).collect()
+    }
+    ///全部目标完成,没有目标时永远不会完成
+    pub fn completed(&self, variables: &[(i32, i32)]) -> bool{
+        !self.is_empty() && self.progress(variables).iter().all(|(current, target)| current >= target)
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_objective_progress(){
+    use super::explore_event::GameEventState;
+    let objectives = ExploreObjectives{
+        task_id: 1,
+        objectives: vec![
+            ObjectiveConfig::ReachTile{ x: 3, y: 4 },
+            ObjectiveConfig::ProgressEvents{ count: 2 },
+            ObjectiveConfig::CollectItem{ item_id: 7, count: 5 },
+        ],
+    };
+    let mut variables = vec![(1, 1)];
+    let mut event = ExploreEvent::new(1, 1, 100, Default::default(), GameEventState::Finished, Point2::new(0, 0), true);
+    event.item = Some((7, 3));
+    objectives.on_event_finished(&event, &mut variables);
+    objectives.on_position(Point2::new(3, 4), &mut variables);
+    assert!(!objectives.completed(&variables));
+    objectives.on_event_finished(&event, &mut variables);
+    assert_eq!(objectives.progress(&variables), vec![(1, 1), (2, 2), (5, 5)]);
+    assert!(objectives.completed(&variables));
+    assert!(!ExploreObjectives::default().completed(&variables));
+}
@@ -0,0 +1,75 @@
+//! 探索id生成
+//!
+//! id结构(高位到低位): 1位保留 | 41位毫秒时间戳(相对EPOCH) | 10位服务器id | 12位序列号
+//! 同一毫秒内序列号耗尽或系统时钟回拨时,借用后续毫秒继续生成,不会阻塞线程
+use std::sync::atomic::{AtomicU64, Ordering};
+///时间戳起点 2022-01-01 00:00:00 UTC
+const EPOCH: u64 = 1_640_995_200_000;
+const SERVER_BITS: u64 = 10;
+const SEQUENCE_BITS: u64 = 12;
+const SERVER_MASK: u64 = (1 << SERVER_BITS) - 1;
+const SEQUENCE_MASK: u64 = (1 << SEQUENCE_BITS) - 1;
+///上一次生成的(时间戳 << SEQUENCE_BITS | 序列号)
+static LAST_STAMP: AtomicU64 = AtomicU64::new(0);
+///根据上一次的时间戳和序列号计算下一个
+#[inline]
+fn next_stamp(last: u64, now: u64) -> u64{
+    let last_ms = last >> SEQUENCE_BITS;
+    if now > last_ms {
+        now << SEQUENCE_BITS
+    }
+    else{
+        //同一毫秒或时钟回拨,沿用上次时间戳递增序列号,序列号溢出时进位到下一毫秒
+        last + 1
+    }
+}
+fn generate(last_stamp: &AtomicU64, server_id: u32, now_ms: u64) -> u64{
+    let now = now_ms.saturating_sub(EPOCH);
+    let mut last = last_stamp.load(Ordering::Acquire);
+    loop {
+        let stamp = next_stamp(last, now);
+        match last_stamp.compare_exchange_weak(last, stamp, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {
+                let ms = stamp >> SEQUENCE_BITS;
+                let sequence = stamp & SEQUENCE_MASK;
+                return ms << (SERVER_BITS + SEQUENCE_BITS) | (server_id as u64 & SERVER_MASK) << SEQUENCE_BITS | sequence;
+            },
+            Err(current) => last = current,
+        }
+    }
+}
+///获取探索id
+pub(crate) fn get_uuid(server_id: u32) -> u64{
+    if server_id as u64 > SERVER_MASK {
+        warn!("server id {} exceeds {} bits, uuid may collide", server_id, SERVER_BITS);
+    }
+    generate(&LAST_STAMP, server_id, shared::get_current_ms().max(0) as u64)
+}
+#[cfg(test)]
+#[test]
+fn test_snowflake_unique(){
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+    //并发生成
+    let stamp = Arc::new(AtomicU64::new(0));
+    let now = EPOCH + 1000;
+    let handles = (0..8).map(|_| {
+        let stamp = stamp.clone();
+        std::thread::spawn(move || (0..10000).map(|_| generate(&stamp, 3, now)).collect::<Vec<_>>())
+    }).collect::<Vec<_>>();
+    let mut ids = BTreeSet::new();
+    for handle in handles {
+        for id in handle.join().unwrap() {
+            assert_eq!(id >> SEQUENCE_BITS & SERVER_MASK, 3);
+            assert!(ids.insert(id));
+        }
+    }
+    assert_eq!(ids.len(), 80000);
+    //时钟回拨后仍然递增
+    let last = *ids.iter().next_back().unwrap();
+    let id = generate(&stamp, 3, now - 500);
+    assert!(id > last);
+    //不同服务器同一时间不冲突
+    let other = generate(&AtomicU64::new(0), 4, now);
+    assert!(!ids.contains(&other));
+}
@@ -0,0 +1,108 @@
+//! 内存存储,不做持久化,用于测试
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use shared::map::Point2;
+use super::super::db_handler::*;
+use super::super::objective::OBJECTIVE_VARIABLE_BASE;
+use super::ExploreStorage;
+#[derive(Default)]
+struct MemoryData{
+    explores: BTreeMap<u64, ExploreInfo>,
+    ///(玩家id, 探索配置id) -> 已完成事件
+    finished_events: BTreeMap<(u64, u32), Vec<ExploreEventInfo>>,
+    ///(玩家id, 探索配置id, 变量类型) -> 值
+    variables: BTreeMap<(u64, u32, i32), i32>,
+    ///(玩家id, 角色id)
+    player_characters: BTreeSet<(u64, u32)>,
+    ///(玩家id, 事件类型, 事件id) 唯一事件
+    unique_events: BTreeSet<(u64, i32, u32)>,
+}
+#[derive(Default)]
+pub struct MemoryStorage{
+    data: Mutex<MemoryData>,
+}
+#[async_trait]
+impl ExploreStorage for MemoryStorage{
+    async fn on_create_explore(&self, player_id: u64, uuid: u64, explore_id: u32, _token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>{
+        let mut data = self.data.lock().unwrap();
+        let current = data.explores.values().find(|e| e.player_id == player_id && e.state == EXPLORE_STATE_NORMAL).cloned();
+        let mut explore = match current {
+            Some(mut current) => {
+                current.finished_events = data.finished_events.get(&(player_id, current.explore_id))
+                    .map(|events| events.iter().filter(|e| e.progress_event == 1).cloned().collect())
+                    .unwrap_or_default();
+                current.variables = data.variables.iter()
+                    .filter(|((pid, scene, _), _)| *pid == player_id && *scene == current.explore_id)
+                    .map(|((_, _, variable_type), value)| (*variable_type, *value))
+                    .collect();
+                current
+            }
+            None => {
+                //清理上一次探索遗留的任务进度
+                data.variables.retain(|(pid, scene, variable_type), _| *pid != player_id || *scene != explore_id || *variable_type < OBJECTIVE_VARIABLE_BASE);
+                let mut explore = ExploreInfo::new(player_id, explore_id, shared::libconfig::common::get_value("DefaultFood").unwrap_or(100), base_point);
+                explore.id = uuid;
+                data.explores.insert(explore.id, explore.clone());
+                explore
+            }
+        };
+        explore.unique_events = data.unique_events.iter()
+            .filter(|(pid, _, _)| *pid == player_id)
+            .map(|(_, event_type, event_id)| (*event_type, *event_id))
+            .collect();
+        Ok(explore)
+    }
+    async fn on_save_explore(&self, explore: &ExploreInfo) -> anyhow::Result<()>{
+        let mut data = self.data.lock().unwrap();
+        let events = data.finished_events.entry((explore.player_id, explore.explore_id)).or_default();
+        for event in explore.get_events() {
+            if events.iter().find(|e| e.event_type == event.event_type && e.event_id == event.event_id).is_none(){
+                events.push(event.clone());
+            }
+        }
+        let finished_event = events.iter().filter(|e| e.progress_event == 1).count() as i32;
+        for (variable_type, value) in explore.variables.iter() {
+            data.variables.insert((explore.player_id, explore.explore_id, *variable_type), *value);
+        }
+        if explore.state != EXPLORE_STATE_NORMAL{
+            data.variables.retain(|(pid, scene, variable_type), _| *pid != explore.player_id || *scene != explore.explore_id || *variable_type < OBJECTIVE_VARIABLE_BASE);
+        }
+        let mut saved = explore.clone();
+        saved.finished_event = finished_event;
+        saved.finished_events.clear();
+        saved.variables.clear();
+        data.explores.insert(saved.id, saved);
+        Ok(())
+    }
+    async fn save_character(&self, player_id: u64, characters: &Vec<u32>) -> anyhow::Result<()>{
+        let mut data = self.data.lock().unwrap();
+        for cid in characters {
+            data.player_characters.insert((player_id, *cid));
+        }
+        Ok(())
+    }
+    async fn on_quit_explore(&self, explore_id: u64) -> anyhow::Result<()>{
+        if let Some(explore) = self.data.lock().unwrap().explores.get_mut(&explore_id){
+            explore.state = EXPLORE_STATE_REMOVED;
+        }
+        Ok(())
+    }
+    async fn on_abandon_explore(&self, player_id: u64) -> anyhow::Result<Vec<u64>>{
+        let mut explores = Vec::new();
+        for explore in self.data.lock().unwrap().explores.values_mut().filter(|e| e.player_id == player_id && e.state == EXPLORE_STATE_NORMAL) {
+            explore.state = EXPLORE_STATE_REMOVED;
+            explores.push(explore.id);
+        }
+        Ok(explores)
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_memory_round_trip(){
+    let storage = MemoryStorage::default();
+    storage.data.lock().unwrap().unique_events.insert((7, 1, 104));
+    futures::executor::block_on(super::test_round_trip(&storage));
+    futures::executor::block_on(super::test_objective_reset(&storage));
+    let explore = futures::executor::block_on(storage.on_create_explore(7, 70004, 1, "", Point2::new(1, 1))).unwrap();
+    assert_eq!(explore.unique_events, vec![(1, 104)]);
+}
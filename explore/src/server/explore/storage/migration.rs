@@ -0,0 +1,35 @@
+//! 数据库版本迁移
+//!
+//! 已执行的版本记录在 explore_schema_version 表中,与平台服的版本表互不影响
+//! db_player_character 等玩家表由平台服迁移创建,探索服需与平台服使用同一个数据库
+///版本记录表
+pub const VERSION_TABLE: &str = "explore_schema_version";
+pub use db_migration::{Migration, pending};
+pub static MYSQL_MIGRATIONS: &[Migration] = &[
+    Migration{ version: 1, name: "init", sql: include_str!("../../../../migrations/mysql/0001_init.sql") },
+];
+pub static SQLITE_MIGRATIONS: &[Migration] = &[
+    Migration{ version: 1, name: "init", sql: include_str!("../../../../migrations/sqlite/0001_init.sql") },
+];
+///版本记录表建表语句
+#[inline]
+pub fn version_table_sql() -> String{
+    db_migration::version_table_sql(VERSION_TABLE)
+}
+///当前版本查询语句
+#[inline]
+pub fn current_version_sql() -> String{
+    db_migration::current_version_sql(VERSION_TABLE)
+}
+///记录已执行版本的语句
+#[inline]
+pub fn insert_version_sql() -> String{
+    db_migration::insert_version_sql(VERSION_TABLE)
+}
+#[cfg(test)]
+#[test]
+fn test_migration_order(){
+    assert!(db_migration::check(MYSQL_MIGRATIONS) && db_migration::check(SQLITE_MIGRATIONS));
+    assert_eq!(MYSQL_MIGRATIONS.len(), SQLITE_MIGRATIONS.len());
+    assert_eq!(pending(MYSQL_MIGRATIONS, Some(1)).count(), MYSQL_MIGRATIONS.len() - 1);
+}
@@ -0,0 +1,104 @@
+//! 探索数据存储
+//!
+//! 通过配置项 storage 选择存储方式: mysql(默认), sqlite, memory
+mod mysql;
+mod sqlite;
+mod memory;
+pub mod migration;
+use shared::map::Point2;
+use once_cell::sync::OnceCell;
+use super::db_handler::ExploreInfo;
+pub use {mysql::MySqlStorage, sqlite::SqliteStorage, memory::MemoryStorage};
+#[async_trait]
+pub trait ExploreStorage: Send + Sync{
+    ///执行数据库迁移,内存存储无需迁移
+    async fn migrate(&self) -> anyhow::Result<()>{
+        Ok(())
+    }
+    ///加载或者创建探索,新建的探索使用生成的uuid作为id
+    async fn on_create_explore(&self, player_id: u64, uuid: u64, explore_id: u32, token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>;
+    ///保存探索快照
+    async fn on_save_explore(&self, explore: &ExploreInfo) -> anyhow::Result<()>;
+    ///保存已入队角色
+    async fn save_character(&self, player_id: u64, characters: &Vec<u32>) -> anyhow::Result<()>;
+    ///退出探索
+    async fn on_quit_explore(&self, explore_id: u64) -> anyhow::Result<()>;
+    ///放弃玩家进行中的探索,返回放弃的探索id
+    async fn on_abandon_explore(&self, player_id: u64) -> anyhow::Result<Vec<u64>>;
+}
+static STORAGE: OnceCell<Box<dyn ExploreStorage>> = OnceCell::new();
+///根据配置初始化存储并执行数据库迁移
+pub fn init() -> anyhow::Result<()>{
+    let storage: Box<dyn ExploreStorage> = match shared::libconfig::config::get_str("storage").unwrap_or_default().as_str() {
+        "memory" => Box::new(MemoryStorage::default()),
+        "sqlite" => Box::new(SqliteStorage::new(&shared::libconfig::config::get_str("sqlite_db").expect("fail to load sqlite_db"))?),
+        _ => Box::new(MySqlStorage::new(&shared::libconfig::config::get_str("player_db").expect("fail to load db_path"))),
+    };
+    shared::server::worker::block_on(storage.migrate())?;
+    set(storage)
+}
+///设置存储,只能设置一次
+pub fn set(storage: Box<dyn ExploreStorage>) -> anyhow::Result<()>{
+    STORAGE.set(storage).map_err(|_| std::io::Error::from(std::io::ErrorKind::AlreadyExists))?;
+    Ok(())
+}
+#[inline]
+pub fn get() -> &'static dyn ExploreStorage{
+    STORAGE.get().expect("explore storage not initialized").as_ref()
+}
+///创建 -> 保存 -> 重新加载后,已完成事件,变量和角色状态保持一致
+#[cfg(test)]
+pub(super) async fn test_round_trip(storage: &dyn ExploreStorage){
+    use super::db_handler::{ExploreCharacterInfo, ExploreEventInfo};
+    let mut explore = storage.on_create_explore(7, 70001, 1, "token", Point2::new(1, 1)).await.unwrap();
+    assert_eq!(explore.id, 70001);
+    explore.finished_events = vec![
+        ExploreEventInfo{ id: 0, scene_id: 1, event_id: 101, event_type: 1, position: sqlx::types::Json(Point2::new(4, 4)), progress_event: 1 },
+        ExploreEventInfo{ id: 0, scene_id: 1, event_id: 103, event_type: 1, position: sqlx::types::Json(Point2::new(12, 10)), progress_event: 0 },
+    ];
+    explore.variables = vec![(1, 10), (2, 20)];
+    explore.characters = vec![ExploreCharacterInfo{ role_id: 10111, health: 50, max_health: 100, state: 1 }];
+    explore.position = Some(sqlx::types::Json(Point2::new(4, 4)));
+    explore.food = 42;
+    storage.on_save_explore(&explore).await.unwrap();
+    let loaded = storage.on_create_explore(7, 70002, 1, "token", Point2::new(1, 1)).await.unwrap();
+    assert_eq!(loaded.id, explore.id);
+    assert_eq!(loaded.food, 42);
+    assert_eq!(loaded.position.map(|p| (p.0.x, p.0.y)), Some((4, 4)));
+    //只恢复推进进度的事件
+    assert_eq!(loaded.finished_events.iter().map(|e| e.event_id).collect::<Vec<_>>(), vec![101]);
+    let mut variables = loaded.variables.clone();
+    variables.sort();
+    assert_eq!(variables, vec![(1, 10), (2, 20)]);
+    assert_eq!(loaded.characters.len(), 1);
+    assert_eq!((loaded.characters[0].role_id, loaded.characters[0].health), (10111, 50));
+    //取消后重新创建新的探索
+    assert_eq!(storage.on_abandon_explore(7).await.unwrap(), vec![explore.id]);
+    let created = storage.on_create_explore(7, 70003, 1, "token", Point2::new(1, 1)).await.unwrap();
+    assert_eq!(created.id, 70003);
+}
+///任务进度只属于一次探索,结束后重新开始同一探索时不会继承
+#[cfg(test)]
+pub(super) async fn test_objective_reset(storage: &dyn ExploreStorage){
+    use super::{db_handler::EXPLORE_STATE_FINISHED, objective::OBJECTIVE_VARIABLE_BASE};
+    crate::config::load_samples();
+    let objectives = super::objective::ExploreObjectives::new(1, 1).unwrap();
+    let mut explore = storage.on_create_explore(8, 80001, 1, "token", Point2::new(1, 1)).await.unwrap();
+    explore.variables = vec![(1, 1)];
+    explore.variables.extend((0..3).map(|index| (OBJECTIVE_VARIABLE_BASE + index, 100)));
+    assert!(objectives.completed(&explore.variables));
+    explore.state = EXPLORE_STATE_FINISHED;
+    storage.on_save_explore(&explore).await.unwrap();
+    let mut next = storage.on_create_explore(8, 80002, 1, "token", Point2::new(1, 1)).await.unwrap();
+    assert_ne!(next.id, explore.id);
+    assert!(!objectives.completed(&next.variables));
+    next.variables = vec![(OBJECTIVE_VARIABLE_BASE, 1)];
+    storage.on_save_explore(&next).await.unwrap();
+    //重新加载进行中的探索,只恢复本次探索的进度,普通变量保留
+    let loaded = storage.on_create_explore(8, 80003, 1, "token", Point2::new(1, 1)).await.unwrap();
+    assert_eq!(loaded.id, next.id);
+    let mut variables = loaded.variables.clone();
+    variables.sort();
+    assert_eq!(variables, vec![(1, 1), (OBJECTIVE_VARIABLE_BASE, 1)]);
+    assert!(!objectives.completed(&loaded.variables));
+}
@@ -0,0 +1,194 @@
+//! mysql存储
+use shared::map::Point2;
+use sqlx::{Row, mysql::MySqlRow};
+use super::super::db_handler::*;
+use super::super::objective::OBJECTIVE_VARIABLE_BASE;
+use super::{ExploreStorage, migration};
+///数据库连接池名
+pub const POOL_NAME: &str = "db_explore";
+impl<'a> sqlx::FromRow<'a, MySqlRow> for ExploreInfo{
+    fn from_row(row: &'a MySqlRow) -> Result<Self, sqlx::Error> {
+        Ok(Self{
+            id: row.try_get("id").unwrap_or_default(),
+            player_id: row.try_get("player_id").unwrap_or_default(),
+            state: row.try_get("state").unwrap_or_default(),
+            explore_id: row.try_get("explore_id").unwrap_or_default(),
+            max_event: row.try_get("max_event").unwrap_or_default(),
+            finished_event: row.try_get("finished_event").unwrap_or_default(),
+            create_time: row.try_get("create_time").unwrap_or(chrono::Local::now()),
+            token: row.try_get("token").unwrap_or_default(),
+            position: row.try_get("position").unwrap_or_default(),
+            food: row.try_get("food").unwrap_or_default(),
+            origin_position: row.try_get("origin_position").unwrap_or_default(),
+            explored_map: row.try_get("explored_map").unwrap_or_default(),
+            characters: Default::default(),
+            finished_events: Default::default(),
+            unique_events: Default::default(),
+            variables: Default::default(),
+        })
+    }
+}
+pub struct MySqlStorage;
+impl MySqlStorage{
+    ///启动连接池
+    pub fn new(db_path: &str) -> Self{
+        shared::db::start_pools(vec![shared::db::DbPoolInfo{db_path: db_path.to_string(), db_name: POOL_NAME.to_string(),max_conn: 20}]);
+        Self
+    }
+}
+#[async_trait]
+impl ExploreStorage for MySqlStorage{
+    async fn migrate(&self) -> anyhow::Result<()>{
+        use sqlx::Executor;
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        pool.as_ref().execute(migration::version_table_sql().as_str()).await.map_err(|e| logthrow!(e,e))?;
+        let current = sqlx::query_as::<_,(Option<i64>,)>(&migration::current_version_sql())
+        .fetch_one(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?.0;
+        for m in migration::pending(migration::MYSQL_MIGRATIONS, current) {
+            info!("apply migration {} {} to {}", m.version, m.name, POOL_NAME);
+            //mysql的DDL会隐式提交,事务只保证数据变更与版本记录一起提交,迁移脚本仍需可重复执行
+            let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
+            (&mut trans).execute(m.sql).await.map_err(|e| logthrow!(e,e))?;
+            sqlx::query(&migration::insert_version_sql())
+            .bind(m.version).bind(m.name).bind(chrono::Local::now().timestamp_millis())
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+            trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_save_explore(&self, explore: &ExploreInfo) -> anyhow::Result<()>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let finish_time = if_else!(explore.state == EXPLORE_STATE_FINISHED,Some(chrono::Local::now()),None);
+        let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        for event in explore.get_events() {
+            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_finished_event WHERE player_id=? AND event_type=? AND event_id=?")
+            .bind(explore.player_id).bind(event.event_type).bind(event.event_id)
+            .fetch_one(&mut trans).await.map_err(|e| logthrow!(e,e))?.0;
+            if count > 0{
+                continue;
+            }
+            sqlx::query("INSERT INTO db_finished_event 
+            (player_id,scene_type,scene_id,event_id,event_type,`position`,progress_event) 
+            VALUES(?,0,?,?,?,?,?)")
+            .bind(explore.player_id)
+            .bind(explore.explore_id)
+            .bind(event.event_id)
+            .bind(event.event_type)
+            .bind(serde_json::to_string(&event.position.0)?)
+            .bind(event.progress_event)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        sqlx::query("UPDATE db_explore SET state=?,food=?,`position`=?,origin_position=?,explored_map=?,finish_time=?,
+            finished_event=(SELECT COUNT(*) FROM db_finished_event WHERE player_id=? AND scene_type=0 AND scene_id=? AND progress_event=1) 
+            WHERE id=?")
+        .bind(explore.state)
+        .bind(explore.food)
+        .bind(serde_json::to_string(&explore.position.as_ref().map(|p| p.0))?)
+        .bind(serde_json::to_string(&explore.origin_position.as_ref().map(|p| p.0))?)
+        .bind(&explore.explored_map)
+        .bind(finish_time)
+        .bind(explore.player_id)
+        .bind(explore.explore_id)
+        .bind(explore.id)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        for character in explore.characters.iter() {
+            sqlx::query("REPLACE INTO db_explore_character (explore_id,role_id,health,max_health,state) VALUES(?,?,?,?,?)")
+            .bind(explore.id)
+            .bind(character.role_id)
+            .bind(character.health)
+            .bind(character.max_health)
+            .bind(character.state)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        let player_id = explore.player_id;
+        let explore_id = explore.explore_id;
+        let variable_list = explore.variables.iter().map(|t| format!("({},0,{},{},{})",player_id,explore_id,t.0,t.1)).collect::<Vec<_>>();
+        if variable_list.len() > 0{
+            let variables = format!("REPLACE INTO global_explore_variables (player_id,scene_type,scene_id,variable_type,value) VALUES {}",
+            variable_list.join(","));
+            sqlx::query(&variables)
+           .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        if explore.state != EXPLORE_STATE_NORMAL{
+            sqlx::query("DELETE FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=? AND variable_type>=?")
+            .bind(player_id).bind(explore_id).bind(OBJECTIVE_VARIABLE_BASE)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+    async fn on_create_explore(&self, player_id: u64, uuid: u64, explore_id: u32, token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let current = sqlx::query_as::<_,ExploreInfo>("SELECT * FROM db_explore WHERE player_id=? AND state=0")
+        .bind(&player_id)
+        .fetch_optional(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        let mut explore = if current.is_some(){
+            info!("explore loaded {:?}",current);
+            let mut current = current.unwrap();
+            let events = sqlx::query_as::<_,ExploreEventInfo>("SELECT * FROM db_finished_event WHERE player_id=? AND progress_event=1 AND scene_type=0 AND scene_id=?")
+            .bind(player_id).bind(current.explore_id)
+            .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+            current.finished_events = events;
+            current.variables = sqlx::query_as("SELECT variable_type,`value` FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=?")
+            .bind(player_id).bind(current.explore_id)
+            .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+            current.characters = sqlx::query_as::<_,ExploreCharacterInfo>("SELECT role_id,health,max_health,state FROM db_explore_character WHERE explore_id=?")
+            .bind(current.id)
+            .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+            current
+        }
+        else{
+            let food = shared::libconfig::common::get_value("DefaultFood").unwrap_or(100);
+            //清理上一次探索遗留的任务进度
+            sqlx::query("DELETE FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=? AND variable_type>=?")
+            .bind(player_id).bind(explore_id).bind(OBJECTIVE_VARIABLE_BASE)
+            .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+            sqlx::query("INSERT INTO db_explore (id,player_id,explore_id,token,food,`position`,origin_position) VALUES(?,?,?,?,?,?,?)")
+            .bind(uuid).bind(player_id).bind(explore_id).bind(token).bind(food).bind(serde_json::to_string(&base_point)?).bind(serde_json::to_string(&base_point)?)
+            .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+            info!("insert new explore {}", uuid);
+            let mut explore = ExploreInfo::new(player_id, explore_id, food, base_point);
+            explore.id = uuid;
+            explore
+        };
+        explore.unique_events = sqlx::query_as("SELECT event_type,event_id FROM db_unique_event WHERE player_id=?")
+        .bind(player_id)
+        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        Ok(explore)
+    }
+    async fn save_character(&self, player_id: u64, charactes: &Vec<u32>) -> anyhow::Result<()>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        for cid in charactes {
+            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_player_character WHERE player_id=? AND role_id=?")
+            .bind(player_id).bind(cid)
+            .fetch_one(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?.0;
+            if count > 0{
+                continue;
+            }
+            sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,1,1)")
+            .bind(cid).bind(player_id)
+            .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_quit_explore(&self, explore_id: u64) -> anyhow::Result<()>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        sqlx::query("UPDATE db_explore SET state=2 WHERE id=?")
+        .bind(explore_id)
+        .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+    async fn on_abandon_explore(&self, player_id: u64) -> anyhow::Result<Vec<u64>>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        let explores = sqlx::query_as::<_,(u64,)>("SELECT id FROM db_explore WHERE player_id=? AND state=0 FOR UPDATE")
+        .bind(player_id)
+        .fetch_all(&mut trans).await.map_err(|e| logthrow!(e,e))?
+        .into_iter().map(|(id,)| id).collect::<Vec<_>>();
+        sqlx::query("UPDATE db_explore SET state=2 WHERE player_id=? AND state=0")
+        .bind(player_id)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(explores)
+    }
+}
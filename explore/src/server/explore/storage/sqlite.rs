@@ -0,0 +1,225 @@
+//! sqlite存储,用于单机部署和测试
+use std::str::FromStr;
+use shared::map::Point2;
+use sqlx::{Row, sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow}};
+use super::super::db_handler::*;
+use super::super::objective::OBJECTIVE_VARIABLE_BASE;
+use super::{ExploreStorage, migration};
+pub struct SqliteStorage{
+    pool: SqlitePool,
+}
+///sqlite不支持u64,json字段以字符串存储
+fn json_point(row: &SqliteRow, column: &str) -> Option<sqlx::types::Json<Point2>>{
+    row.try_get::<Option<String>, _>(column).ok()
+        .flatten()
+        .and_then(|s| serde_json::from_str::<Point2>(&s).ok())
+        .map(sqlx::types::Json)
+}
+fn explore_from_row(row: &SqliteRow) -> ExploreInfo{
+    let mut explore = ExploreInfo::default();
+    explore.id = row.try_get::<i64, _>("id").unwrap_or_default() as u64;
+    explore.player_id = row.try_get::<i64, _>("player_id").unwrap_or_default() as u64;
+    explore.state = row.try_get("state").unwrap_or_default();
+    explore.explore_id = row.try_get::<i64, _>("explore_id").unwrap_or_default() as u32;
+    explore.max_event = row.try_get("max_event").unwrap_or_default();
+    explore.finished_event = row.try_get("finished_event").unwrap_or_default();
+    explore.token = row.try_get("token").unwrap_or_default();
+    explore.position = json_point(row, "position");
+    explore.origin_position = json_point(row, "origin_position");
+    explore.food = row.try_get("food").unwrap_or_default();
+    explore.explored_map = row.try_get::<Option<Vec<u8>>, _>("explored_map").ok().flatten().unwrap_or_default();
+    explore
+}
+impl SqliteStorage{
+    pub fn new(db_path: &str) -> anyhow::Result<Self>{
+        let options = SqliteConnectOptions::from_str(db_path)?.create_if_missing(true);
+        Ok(Self{
+            pool: SqlitePoolOptions::new().max_connections(4).connect_lazy_with(options),
+        })
+    }
+    #[inline]
+    pub fn pool(&self) -> &SqlitePool{
+        &self.pool
+    }
+}
+#[async_trait]
+impl ExploreStorage for SqliteStorage{
+    async fn migrate(&self) -> anyhow::Result<()>{
+        use sqlx::Executor;
+        self.pool.execute(migration::version_table_sql().as_str()).await.map_err(|e| logthrow!(e,e))?;
+        let current = sqlx::query_as::<_,(Option<i64>,)>(&migration::current_version_sql())
+        .fetch_one(&self.pool).await.map_err(|e| logthrow!(e,e))?.0;
+        for m in migration::pending(migration::SQLITE_MIGRATIONS, current) {
+            info!("apply migration {} {}", m.version, m.name);
+            let mut trans = self.pool.begin().await.map_err(|e| logthrow!(e,e))?;
+            (&mut trans).execute(m.sql).await.map_err(|e| logthrow!(e,e))?;
+            sqlx::query(&migration::insert_version_sql())
+            .bind(m.version).bind(m.name).bind(chrono::Local::now().timestamp_millis())
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+            trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_create_explore(&self, player_id: u64, uuid: u64, explore_id: u32, token: &str, base_point: Point2) -> anyhow::Result<ExploreInfo>{
+        let current = sqlx::query("SELECT * FROM db_explore WHERE player_id=? AND state=0")
+        .bind(player_id as i64)
+        .fetch_optional(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        let mut explore = match current {
+            Some(row) => {
+                let mut current = explore_from_row(&row);
+                info!("explore loaded {:?}",current);
+                current.finished_events = sqlx::query("SELECT id,scene_id,event_id,event_type,`position`,progress_event FROM db_finished_event WHERE player_id=? AND progress_event=1 AND scene_type=0 AND scene_id=?")
+                .bind(player_id as i64).bind(current.explore_id)
+                .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?
+                .iter()
+                .map(|row| ExploreEventInfo{
+                    id: row.try_get::<i64, _>("id").unwrap_or_default() as u64,
+                    scene_id: row.try_get::<i64, _>("scene_id").unwrap_or_default() as u64,
+                    event_id: row.try_get::<i64, _>("event_id").unwrap_or_default() as u32,
+                    event_type: row.try_get("event_type").unwrap_or_default(),
+                    position: json_point(row, "position").unwrap_or(sqlx::types::Json(Point2::default())),
+                    progress_event: row.try_get("progress_event").unwrap_or_default(),
+                })
+                .collect();
+                current.variables = sqlx::query_as("SELECT variable_type,`value` FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=?")
+                .bind(player_id as i64).bind(current.explore_id)
+                .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+                current.characters = sqlx::query_as::<_,(i64,i32,i32,i32)>("SELECT role_id,health,max_health,state FROM db_explore_character WHERE explore_id=?")
+                .bind(current.id as i64)
+                .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?
+                .into_iter()
+                .map(|(role_id, health, max_health, state)| ExploreCharacterInfo{ role_id: role_id as u32, health, max_health, state })
+                .collect();
+                current
+            }
+            None => {
+                let food = shared::libconfig::common::get_value("DefaultFood").unwrap_or(100);
+                //清理上一次探索遗留的任务进度
+                sqlx::query("DELETE FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=? AND variable_type>=?")
+                .bind(player_id as i64).bind(explore_id).bind(OBJECTIVE_VARIABLE_BASE)
+                .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+                sqlx::query("INSERT INTO db_explore (id,player_id,explore_id,token,food,`position`,origin_position) VALUES(?,?,?,?,?,?,?)")
+                .bind(uuid as i64).bind(player_id as i64).bind(explore_id).bind(token).bind(food).bind(serde_json::to_string(&base_point)?).bind(serde_json::to_string(&base_point)?)
+                .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+                info!("insert new explore {}", uuid);
+                let mut explore = ExploreInfo::new(player_id, explore_id, food, base_point);
+                explore.id = uuid;
+                explore
+            }
+        };
+        explore.unique_events = sqlx::query_as::<_,(i32,i64)>("SELECT event_type,event_id FROM db_unique_event WHERE player_id=?")
+        .bind(player_id as i64)
+        .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?
+        .into_iter()
+        .map(|(event_type, event_id)| (event_type, event_id as u32))
+        .collect();
+        Ok(explore)
+    }
+    async fn on_save_explore(&self, explore: &ExploreInfo) -> anyhow::Result<()>{
+        let finish_time = if_else!(explore.state == EXPLORE_STATE_FINISHED,Some(chrono::Local::now().to_rfc3339()),None);
+        let mut trans = self.pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        for event in explore.get_events() {
+            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_finished_event WHERE player_id=? AND event_type=? AND event_id=?")
+            .bind(explore.player_id as i64).bind(event.event_type).bind(event.event_id)
+            .fetch_one(&mut trans).await.map_err(|e| logthrow!(e,e))?.0;
+            if count > 0{
+                continue;
+            }
+            sqlx::query("INSERT INTO db_finished_event 
+            (player_id,scene_type,scene_id,event_id,event_type,`position`,progress_event) 
+            VALUES(?,0,?,?,?,?,?)")
+            .bind(explore.player_id as i64)
+            .bind(explore.explore_id)
+            .bind(event.event_id)
+            .bind(event.event_type)
+            .bind(serde_json::to_string(&event.position.0)?)
+            .bind(event.progress_event)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        sqlx::query("UPDATE db_explore SET state=?,food=?,`position`=?,origin_position=?,explored_map=?,finish_time=?,
+            finished_event=(SELECT COUNT(*) FROM db_finished_event WHERE player_id=? AND scene_type=0 AND scene_id=? AND progress_event=1) 
+            WHERE id=?")
+        .bind(explore.state)
+        .bind(explore.food)
+        .bind(serde_json::to_string(&explore.position.as_ref().map(|p| p.0))?)
+        .bind(serde_json::to_string(&explore.origin_position.as_ref().map(|p| p.0))?)
+        .bind(&explore.explored_map)
+        .bind(finish_time)
+        .bind(explore.player_id as i64)
+        .bind(explore.explore_id)
+        .bind(explore.id as i64)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        for character in explore.characters.iter() {
+            sqlx::query("REPLACE INTO db_explore_character (explore_id,role_id,health,max_health,state) VALUES(?,?,?,?,?)")
+            .bind(explore.id as i64)
+            .bind(character.role_id)
+            .bind(character.health)
+            .bind(character.max_health)
+            .bind(character.state)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        for (variable_type, value) in explore.variables.iter() {
+            sqlx::query("REPLACE INTO global_explore_variables (player_id,scene_type,scene_id,variable_type,`value`) VALUES(?,0,?,?,?)")
+            .bind(explore.player_id as i64)
+            .bind(explore.explore_id)
+            .bind(variable_type)
+            .bind(value)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        if explore.state != EXPLORE_STATE_NORMAL{
+            sqlx::query("DELETE FROM global_explore_variables WHERE player_id=? AND scene_type=0 AND scene_id=? AND variable_type>=?")
+            .bind(explore.player_id as i64).bind(explore.explore_id).bind(OBJECTIVE_VARIABLE_BASE)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+    async fn save_character(&self, player_id: u64, charactes: &Vec<u32>) -> anyhow::Result<()>{
+        for cid in charactes {
+            let count = sqlx::query_as::<_,(i32,)>("SELECT COUNT(*) FROM db_player_character WHERE player_id=? AND role_id=?")
+            .bind(player_id as i64).bind(cid)
+            .fetch_one(&self.pool).await.map_err(|e| logthrow!(e,e))?.0;
+            if count > 0{
+                continue;
+            }
+            sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,1,1)")
+            .bind(cid).bind(player_id as i64)
+            .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_quit_explore(&self, explore_id: u64) -> anyhow::Result<()>{
+        sqlx::query("UPDATE db_explore SET state=2 WHERE id=?")
+        .bind(explore_id as i64)
+        .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+    async fn on_abandon_explore(&self, player_id: u64) -> anyhow::Result<Vec<u64>>{
+        let mut trans = self.pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        let explores = sqlx::query_as::<_,(i64,)>("SELECT id FROM db_explore WHERE player_id=? AND state=0")
+        .bind(player_id as i64)
+        .fetch_all(&mut trans).await.map_err(|e| logthrow!(e,e))?
+        .into_iter().map(|(id,)| id as u64).collect::<Vec<_>>();
+        sqlx::query("UPDATE db_explore SET state=2 WHERE player_id=? AND state=0")
+        .bind(player_id as i64)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(explores)
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_sqlite_round_trip(){
+    let path = std::env::temp_dir().join(format!("explore_storage_{}.db", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let storage = SqliteStorage::new(&format!("sqlite://{}", path.display())).unwrap();
+        storage.migrate().await.unwrap();
+        //重复迁移不会重复执行脚本
+        storage.migrate().await.unwrap();
+        super::test_round_trip(&storage).await;
+        super::test_objective_reset(&storage).await;
+    });
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,124 @@
+//! 探索登入令牌
+//!
+//! 令牌格式: player_id.explore_uuid.plat_server.expire.nonce.signature(16进制)
+//! 签名为HMAC-SHA256,密钥配置在 token_secret,多个探索服与平台服需使用相同密钥
+use std::fmt::Write;
+use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use sha2::Sha256;
+type HmacSha256 = Hmac<Sha256>;
+///默认令牌有效期
+const DEFAULT_TOKEN_TTL: i64 = 5 * 60 * 1000;
+///示例配置中的密钥,不能用于部署
+const SAMPLE_TOKEN_SECRET: &str = "change-me";
+///签名密钥
+static TOKEN_SECRET: OnceCell<Vec<u8>> = OnceCell::new();
+///加载签名密钥,未配置或使用示例密钥时启动失败
+pub fn init() -> anyhow::Result<()>{
+    let secret = match shared::libconfig::config::get_str("token_secret") {
+        Some(secret) if secret.trim() == SAMPLE_TOKEN_SECRET => {
+            error!("token_secret is the sample value, set a private secret");
+            return shared::error::any_err(std::io::ErrorKind::InvalidInput);
+        },
+        Some(secret) if !secret.trim().is_empty() => secret.into_bytes(),
+        _ => {
+            error!("token_secret not configured");
+            return shared::error::any_err(std::io::ErrorKind::InvalidInput);
+        }
+    };
+    TOKEN_SECRET.set(secret).map_err(|_| std::io::Error::from(std::io::ErrorKind::AlreadyExists))?;
+    Ok(())
+}
+#[inline]
+fn secret() -> &'static [u8]{
+    TOKEN_SECRET.get().expect("token secret not initialized")
+}
+///令牌内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessToken{
+    pub player_id: u64,
+    pub explore_uuid: u64,
+    pub plat_server: u64,
+    ///过期时间(毫秒)
+    pub expire: i64,
+    pub nonce: u64,
+}
+impl AccessToken{
+    fn payload(&self) -> String{
+        format!("{:x}.{:x}.{:x}.{:x}.{:x}", self.player_id, self.explore_uuid, self.plat_server, self.expire, self.nonce)
+    }
+    fn sign(&self, secret: &[u8]) -> String{
+        let payload = self.payload();
+        let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts any key length");
+        mac.update(payload.as_bytes());
+        let mut token = payload;
+        token.push('.');
+        for b in mac.finalize().into_bytes() {
+            write!(token, "{:02x}", b).ok();
+        }
+        token
+    }
+    fn parse(secret: &[u8], token: &str, now: i64) -> anyhow::Result<Self>{
+        let (payload, signature) = token.rsplit_once('.').ok_or_else(|| shared::error::unpack_err())?;
+        //按字节切分签名,非ascii字符直接拒绝
+        if !signature.is_ascii() || signature.len() % 2 != 0 {
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+        let signature = signature.as_bytes().chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap_or_default(), 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| logthrow!(e, shared::error::unpack_err()))?;
+        let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts any key length");
+        mac.update(payload.as_bytes());
+        if mac.verify_slice(&signature).is_err() {
+            return shared::error::any_err(std::io::ErrorKind::PermissionDenied);
+        }
+        let fields = payload.split('.')
+        .map(|f| u64::from_str_radix(f, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| logthrow!(e, shared::error::unpack_err()))?;
+        if fields.len() != 5 {
+            return shared::error::any_err(std::io::ErrorKind::InvalidData);
+        }
+        let token = Self{
+            player_id: fields[0],
+            explore_uuid: fields[1],
+            plat_server: fields[2],
+            expire: fields[3] as i64,
+            nonce: fields[4],
+        };
+        if token.expire < now {
+            return shared::error::any_err(std::io::ErrorKind::TimedOut);
+        }
+        Ok(token)
+    }
+}
+///签发令牌
+pub fn issue(player_id: u64, explore_uuid: u64, plat_server: u32) -> String{
+    let ttl = shared::libconfig::config::get("token_ttl").unwrap_or(DEFAULT_TOKEN_TTL);
+    AccessToken{
+        player_id,
+        explore_uuid,
+        plat_server: plat_server as u64,
+        expire: shared::time::get_current_ms() + ttl,
+        nonce: rand::thread_rng().gen(),
+    }.sign(secret())
+}
+///校验令牌签名及有效期
+pub fn verify(token: &str) -> anyhow::Result<AccessToken>{
+    AccessToken::parse(secret(), token, shared::time::get_current_ms())
+}
+#[cfg(test)]
+#[test]
+fn test_access_token(){
+    let token = AccessToken{ player_id: 10001, explore_uuid: 0x1234_5678, plat_server: 3, expire: 1000, nonce: 42 };
+    let signed = token.sign(b"secret");
+    assert_eq!(AccessToken::parse(b"secret", &signed, 999).unwrap(), token);
+    assert!(AccessToken::parse(b"secret", &signed, 1001).is_err());
+    assert!(AccessToken::parse(b"other", &signed, 999).is_err());
+    let forged = signed.replacen("2711", "2712", 1);
+    assert!(AccessToken::parse(b"secret", &forged, 999).is_err());
+    //签名中的多字节字符不会导致panic
+    assert!(AccessToken::parse(b"secret", "1.2.3.4.5.aéa", 999).is_err());
+}
@@ -1,26 +1,84 @@
 use shared::map::{Point2, Map};
-use super::explore_event::{ExploreEvent, EventInfo};
+use shared::proto::EExploreEventType;
+use protobuf::ProtobufEnum;
+use super::explore_event::{ExploreEvent, EventInfo, GameEventState};
 use super::explore_player::ExplorePlayer;
 pub struct ExploreTrigger{
+    ///探索配置id
+    config_id: u32,
     event_index: u32,
     event_uid: u64,
+    ///已生成且未触发的地图事件
+    events: Vec<ExploreEvent>,
+    ///已触发的事件队列
     trigger_events: Vec<ExploreEvent>,
+    ///本次探索完成的事件
+    finished_events: Vec<ExploreEvent>,
     player_position: Point2,
     ///玩家移动速度
     player_speed: u32,
+    hex: bool,
+    initialized: bool,
 }
 impl ExploreTrigger {
-    pub fn new(_event_id: u32) -> Self{
+    pub fn new(event_id: u32) -> Self{
         Self{
+            config_id: event_id,
             event_uid: 1u64,
             event_index: 1,
+            events: Default::default(),
             trigger_events: Default::default(),
+            finished_events: Default::default(),
             player_position: Default::default(),
             player_speed: shared::libconfig::common::get_value("MovementSpeed").unwrap_or(10),
+            hex: false,
+            initialized: false,
         }
     }
     ///initialize
-    pub fn init(&mut self) -> anyhow::Result<()> {
+    /// 
+    /// 根据配置在地图上生成事件,finished为已完成的(事件类型,事件id),不再生成
+    pub fn init(&mut self, map: &Map, hex: bool, finished: &[(i32, u32)]) -> anyhow::Result<()> {
+        //重连时不重新生成事件
+        if self.initialized{
+            return Ok(());
+        }
+        self.hex = hex;
+        for config in crate::config::explore_event::get_events(self.config_id) {
+            if finished.contains(&(config.event_type, config.event_id)){
+                continue;
+            }
+            let event_type = match EExploreEventType::from_i32(config.event_type){
+                Some(t) => t,
+                None => {
+                    warn!("explore {} event {} has invalid event type {}", self.config_id, config.id, config.event_type);
+                    continue;
+                }
+            };
+            let mut position = Point2::new(config.x, config.y);
+            map.bind_point(&mut position);
+            if map.iter().find(|point| point.id() == position.id()).is_none(){
+                warn!("explore {} event {} out of map {:?}", self.config_id, config.id, position);
+                continue;
+            }
+            let mut event = ExploreEvent::new(
+                self.config_id as u64,
+                map.map_id(),
+                config.event_id,
+                event_type,
+                GameEventState::Unfinished,
+                position,
+                config.progress_event
+            );
+            event.id = self.event_uid;
+            event.trigger_range = config.trigger_range;
+            event.enemy_id = config.enemy_id;
+            event.item = Some((config.item_id, config.item_count)).filter(|(id, count)| *id > 0 && *count > 0);
+            self.event_uid += 1;
+            self.events.push(event);
+        }
+        info!("explore {} spawn {} events", self.config_id, self.events.len());
+        self.initialized = true;
         Ok(())
     }
     ///触发事件
@@ -29,11 +87,88 @@ impl ExploreTrigger {
         if position != self.player_position{
             self.player_position = position;
         }
-        //TODO
+        if !player.trigger_enabled{
+            return;
+        }
+        let hex = self.hex;
+        let mut index = 0;
+        while index < self.events.len() {
+            let event = &self.events[index];
+            if event.state == GameEventState::Unfinished && super::get_distance(event.position, position, hex) <= event.trigger_range{
+                let event = self.events.remove(index);
+                info!("explore {} trigger event {} ({:?}) at {:?}", self.config_id, event.id, (event.event_type, event.event_id), position);
+                self.trigger_events.push(event);
+            }
+            else{
+                index += 1;
+            }
+        }
     }
     ///当前事件为空
     pub fn empty(&self) -> bool{
-        //TODO
         self.trigger_events.is_empty()
     }
+    ///当前待处理的事件
+    #[inline]
+    pub fn current(&self) -> Option<&ExploreEvent>{
+        self.trigger_events.first()
+    }
+    #[inline]
+    pub fn current_mut(&mut self) -> Option<&mut ExploreEvent>{
+        self.trigger_events.first_mut()
+    }
+    ///完成当前事件并移出队列
+    pub fn finish_current(&mut self) -> Option<&ExploreEvent>{
+        if self.trigger_events.is_empty(){
+            return None;
+        }
+        let mut event = self.trigger_events.remove(0);
+        event.state = GameEventState::Finished;
+        info!("explore {} event {} finished", self.config_id, event.id);
+        self.finished_events.push(event);
+        self.finished_events.last()
+    }
+    ///取消当前事件,事件放回地图等待再次触发
+    pub fn cancel_current(&mut self){
+        if self.trigger_events.is_empty(){
+            return;
+        }
+        let mut event = self.trigger_events.remove(0);
+        event.notify_state = false;
+        info!("explore {} event {} canceled", self.config_id, event.id);
+        self.events.push(event);
+    }
+    ///本次探索完成的事件
+    #[inline]
+    pub fn finished_events(&self) -> &Vec<ExploreEvent>{
+        &self.finished_events
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_trigger_range(){
+    crate::config::load_samples();
+    let map = crate::config::explore_map::get_map(1).unwrap().build();
+    let mut trigger = ExploreTrigger::new(1);
+    trigger.init(&map, false, &[(1, 102)]).unwrap();
+    //已完成的事件不再生成
+    assert_eq!(trigger.events.len(), 3);
+    assert!(trigger.events.iter().all(|event| event.event_id != 102));
+    let mut player = ExplorePlayer::default();
+    player.trigger_enabled = true;
+    trigger.trigger(&mut player, &map, Point2::new(1, 1));
+    assert!(trigger.empty());
+    //事件101在(4,4),触发范围1
+    trigger.trigger(&mut player, &map, Point2::new(5, 5));
+    assert_eq!(trigger.current().map(|event| event.event_id), Some(101));
+    trigger.finish_current();
+    //事件103在(12,10),触发范围2,事件104触发范围0
+    trigger.trigger(&mut player, &map, Point2::new(15, 11));
+    assert!(trigger.empty());
+    trigger.trigger(&mut player, &map, Point2::new(14, 10));
+    assert_eq!(trigger.current().map(|event| event.event_id), Some(103));
+    trigger.finish_current();
+    trigger.trigger(&mut player, &map, Point2::new(16, 16));
+    assert_eq!(trigger.current().map(|event| event.event_id), Some(104));
+    assert_eq!(trigger.finished_events().len(), 2);
 }
@@ -0,0 +1,93 @@
+//! 运行指标
+//!
+//! 以prometheus文本格式输出,挂载在 bind_web_port 的 /metrics 上
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+///耗时统计
+#[derive(Debug, Default, Clone, Copy)]
+struct Timing{
+    count: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+impl Timing{
+    fn observe(&mut self, elapsed: Duration){
+        let us = elapsed.as_micros() as u64;
+        self.count += 1;
+        self.sum_us += us;
+        self.max_us = self.max_us.max(us);
+    }
+}
+///消息处理耗时(协议号)
+static MSG_TIMINGS: Lazy<Mutex<BTreeMap<u16, Timing>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+///数据库操作耗时(操作名)
+static DB_TIMINGS: Lazy<Mutex<BTreeMap<&'static str, Timing>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+///探索保存失败次数
+static SAVE_FAILURES: AtomicU64 = AtomicU64::new(0);
+///玩家心跳超时次数
+static HEARTBEAT_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+///平台服通道心跳超时次数
+static CHANNEL_HEARTBEAT_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+///记录消息处理耗时
+pub fn observe_msg(opcode: u16, elapsed: Duration){
+    MSG_TIMINGS.lock().unwrap().entry(opcode).or_default().observe(elapsed);
+}
+///记录数据库操作耗时
+pub fn observe_db(query: &'static str, elapsed: Duration){
+    DB_TIMINGS.lock().unwrap().entry(query).or_default().observe(elapsed);
+}
+#[inline]
+pub fn inc_save_failure(){
+    SAVE_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+#[inline]
+pub fn inc_heartbeat_timeout(){
+    HEARTBEAT_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+#[inline]
+pub fn inc_channel_heartbeat_timeout(){
+    CHANNEL_HEARTBEAT_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+fn write_timings<K: std::fmt::Display>(out: &mut String, name: &str, label: &str, help: &str, timings: &BTreeMap<K, Timing>){
+    writeln!(out, "# HELP {}_seconds {}", name, help).ok();
+    writeln!(out, "# TYPE {}_seconds summary", name).ok();
+    for (key, t) in timings {
+        writeln!(out, "{}_seconds_sum{{{}=\"{}\"}} {}", name, label, key, t.sum_us as f64 / 1_000_000.0).ok();
+        writeln!(out, "{}_seconds_count{{{}=\"{}\"}} {}", name, label, key, t.count).ok();
+    }
+    writeln!(out, "# TYPE {}_max_seconds gauge", name).ok();
+    for (key, t) in timings {
+        writeln!(out, "{}_max_seconds{{{}=\"{}\"}} {}", name, label, key, t.max_us as f64 / 1_000_000.0).ok();
+    }
+}
+///输出全部指标
+pub fn render() -> String{
+    let mut out = String::new();
+    writeln!(out, "# HELP explore_active_explores Explores currently running").ok();
+    writeln!(out, "# TYPE explore_active_explores gauge").ok();
+    writeln!(out, "explore_active_explores {}", super::entry::get_bearing()).ok();
+    {
+        let timings = MSG_TIMINGS.lock().unwrap();
+        writeln!(out, "# HELP explore_msg_total Client messages handled by opcode").ok();
+        writeln!(out, "# TYPE explore_msg_total counter").ok();
+        for (opcode, t) in timings.iter() {
+            writeln!(out, "explore_msg_total{{opcode=\"{}\"}} {}", opcode, t.count).ok();
+        }
+        write_timings(&mut out, "explore_msg_duration", "opcode", "Client message handling latency", &timings);
+    }
+    write_timings(&mut out, "explore_db_query_duration", "query", "Database operation latency", &DB_TIMINGS.lock().unwrap());
+    writeln!(out, "# HELP explore_save_failures_total Explore snapshots that failed to save").ok();
+    writeln!(out, "# TYPE explore_save_failures_total counter").ok();
+    writeln!(out, "explore_save_failures_total {}", SAVE_FAILURES.load(Ordering::Relaxed)).ok();
+    writeln!(out, "# HELP explore_heartbeat_timeouts_total Player sessions dropped for missing heartbeats").ok();
+    writeln!(out, "# TYPE explore_heartbeat_timeouts_total counter").ok();
+    writeln!(out, "explore_heartbeat_timeouts_total {}", HEARTBEAT_TIMEOUTS.load(Ordering::Relaxed)).ok();
+    writeln!(out, "# HELP explore_channel_heartbeat_timeouts_total Plat server channels dropped for missing heartbeats").ok();
+    writeln!(out, "# TYPE explore_channel_heartbeat_timeouts_total counter").ok();
+    writeln!(out, "explore_channel_heartbeat_timeouts_total {}", CHANNEL_HEARTBEAT_TIMEOUTS.load(Ordering::Relaxed)).ok();
+    out
+}
@@ -1,4 +1,7 @@
 pub mod start;
 mod entry;
+mod registry;
+mod admin;
+mod metrics;
 mod channel;
 mod explore;
\ No newline at end of file
@@ -0,0 +1,209 @@
+//! 探索注册表
+//!
+//! 按玩家id分片加锁,避免数千探索同时访问时争用同一把全局锁,并维护探索uuid到玩家id的索引
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+///分片数量
+const SHARD_COUNT: usize = 64;
+///探索生命周期事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryEvent{
+    ///探索创建(玩家id,探索uuid)
+    Created(u64, u64),
+    ///玩家连接到探索(玩家id,探索uuid)
+    Attached(u64, u64),
+    ///探索关闭(玩家id,探索uuid)
+    Closed(u64, u64),
+}
+struct Entry<V>{
+    uuid: u64,
+    value: V,
+}
+///分片注册表
+pub struct ShardedRegistry<V>{
+    shards: Vec<RwLock<HashMap<u64, Entry<V>>>>,
+    ///探索uuid -> 玩家id
+    uuids: Vec<RwLock<HashMap<u64, u64>>>,
+    len: AtomicUsize,
+    events: tokio::sync::broadcast::Sender<RegistryEvent>,
+}
+#[inline]
+fn shard_index(key: u64) -> usize{
+    (key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 58) as usize % SHARD_COUNT
+}
+impl<V> ShardedRegistry<V>{
+    pub fn new() -> Self{
+        let (events, _) = tokio::sync::broadcast::channel(1024);
+        Self{
+            shards: (0..SHARD_COUNT).map(|_| RwLock::new(HashMap::new())).collect(),
+            uuids: (0..SHARD_COUNT).map(|_| RwLock::new(HashMap::new())).collect(),
+            len: AtomicUsize::new(0),
+            events,
+        }
+    }
+    ///订阅生命周期事件
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<RegistryEvent>{
+        self.events.subscribe()
+    }
+    #[inline]
+    fn notify(&self, event: RegistryEvent){
+        //没有订阅者时发送失败,忽略
+        self.events.send(event).ok();
+    }
+    ///注册探索,返回被替换的旧探索,替换时先通知旧探索关闭
+    pub fn insert(&self, player_id: u64, uuid: u64, value: V) -> Option<V>{
+        let old = self.shards[shard_index(player_id)].write().unwrap().insert(player_id, Entry{ uuid, value });
+        match old.as_ref() {
+            Some(old) => {
+                if old.uuid != uuid {
+                    self.uuids[shard_index(old.uuid)].write().unwrap().remove(&old.uuid);
+                }
+                self.notify(RegistryEvent::Closed(player_id, old.uuid));
+            },
+            None => {
+                self.len.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.uuids[shard_index(uuid)].write().unwrap().insert(uuid, player_id);
+        self.notify(RegistryEvent::Created(player_id, uuid));
+        old.map(|e| e.value)
+    }
+    ///移除探索
+    pub fn remove(&self, player_id: u64) -> Option<V>{
+        let entry = self.shards[shard_index(player_id)].write().unwrap().remove(&player_id)?;
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        {
+            let mut uuids = self.uuids[shard_index(entry.uuid)].write().unwrap();
+            if uuids.get(&entry.uuid) == Some(&player_id) {
+                uuids.remove(&entry.uuid);
+            }
+        }
+        self.notify(RegistryEvent::Closed(player_id, entry.uuid));
+        Some(entry.value)
+    }
+    ///玩家连接到探索
+    pub fn attach<R>(&self, player_id: u64, f: impl FnOnce(&V) -> R) -> Option<R>{
+        let (uuid, ret) = {
+            let shard = self.shards[shard_index(player_id)].read().unwrap();
+            let entry = shard.get(&player_id)?;
+            (entry.uuid, f(&entry.value))
+        };
+        self.notify(RegistryEvent::Attached(player_id, uuid));
+        Some(ret)
+    }
+    ///访问玩家的探索
+    pub fn with<R>(&self, player_id: u64, f: impl FnOnce(&V) -> R) -> Option<R>{
+        self.shards[shard_index(player_id)].read().unwrap().get(&player_id).map(|e| f(&e.value))
+    }
+    ///根据探索uuid查找玩家id
+    pub fn player_of(&self, uuid: u64) -> Option<u64>{
+        self.uuids[shard_index(uuid)].read().unwrap().get(&uuid).copied()
+    }
+    ///探索数量
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.len.load(Ordering::Relaxed)
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+    ///所有玩家id
+    pub fn keys(&self) -> Vec<u64>{
+        self.shards.iter().flat_map(|shard| shard.read().unwrap().keys().copied().collect::<Vec<_>>()).collect()
+    }
+}
+impl<V> Default for ShardedRegistry<V>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_registry(){
+    let registry = ShardedRegistry::new();
+    let mut events = registry.subscribe();
+    assert_eq!(registry.insert(1, 100, "a"), None);
+    assert_eq!(registry.insert(2, 200, "b"), None);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.player_of(200), Some(2));
+    assert_eq!(registry.attach(1, |v| *v), Some("a"));
+    //重新创建探索,旧uuid失效
+    assert_eq!(registry.insert(1, 101, "c"), Some("a"));
+    assert_eq!(registry.player_of(100), None);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.remove(2), Some("b"));
+    assert_eq!(registry.player_of(200), None);
+    assert_eq!(registry.keys(), vec![1]);
+    let expected = [
+        RegistryEvent::Created(1, 100),
+        RegistryEvent::Created(2, 200),
+        RegistryEvent::Attached(1, 100),
+        RegistryEvent::Closed(1, 100),
+        RegistryEvent::Created(1, 101),
+        RegistryEvent::Closed(2, 200),
+    ];
+    for event in expected {
+        assert_eq!(events.try_recv().unwrap(), event);
+    }
+}
+///对比单锁BTreeMap与分片注册表的并发查询性能,分片注册表不能更慢
+///cargo test --release -p explore bench_registry -- --nocapture
+#[cfg(test)]
+#[test]
+fn bench_registry(){
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+    const EXPLORES: u64 = 5000;
+    const THREADS: u64 = 8;
+    const OPS: u64 = 100_000;
+    let single = Arc::new(Mutex::new((0..EXPLORES).map(|i| (i, i)).collect::<BTreeMap<u64, u64>>()));
+    let start = Instant::now();
+    let handles = (0..THREADS).map(|t| {
+        let single = single.clone();
+        std::thread::spawn(move || {
+            for i in 0..OPS {
+                let key = (i * THREADS + t) % EXPLORES;
+                if i % 100 == 0 {
+                    let mut map = single.lock().unwrap();
+                    map.remove(&key);
+                    map.insert(key, key);
+                }
+                else {
+                    assert!(single.lock().unwrap().get(&key).is_some());
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    let single_elapsed = start.elapsed();
+    let sharded = Arc::new(ShardedRegistry::new());
+    for i in 0..EXPLORES {
+        sharded.insert(i, i, i);
+    }
+    let start = Instant::now();
+    let handles = (0..THREADS).map(|t| {
+        let sharded = sharded.clone();
+        std::thread::spawn(move || {
+            for i in 0..OPS {
+                let key = (i * THREADS + t) % EXPLORES;
+                if i % 100 == 0 {
+                    sharded.remove(key);
+                    sharded.insert(key, key, key);
+                }
+                else {
+                    assert!(sharded.with(key, |v| *v).is_some());
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    let sharded_elapsed = start.elapsed();
+    println!("{} threads x {} ops on {} explores: single lock {:?}, sharded {:?}", THREADS, OPS, EXPLORES, single_elapsed, sharded_elapsed);
+    //单核时没有锁争用,分片没有收益
+    if std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) > 1 {
+        assert!(sharded_elapsed <= single_elapsed, "sharded registry slower than single lock");
+    }
+}
@@ -29,12 +29,18 @@ pub fn start() -> anyhow::Result<()> {
     }));
 
     load_configs()?;
+    super::explore::token::init()?;
     worker::init(0)?;
-    shared::db::start_pools(vec![shared::db::DbPoolInfo{db_path: shared::libconfig::config::get_str("player_db").expect("fail to load db_path"), db_name: "bg_db_server".to_string(),max_conn: 20}]);
+    super::explore::storage::init()?;
+    //只执行数据库迁移
+    if env::args().any(|arg| arg == "--migrate"){
+        info!("explore database migrated");
+        return Ok(());
+    }
     let web_port: String = shared::libconfig::config::get("bind_web_port").expect("fail to load web port from config");
     worker::spawn(
         HttpServer::new(|| {
-            App::new().service(reload)
+            App::new().service(reload).configure(super::admin::config)
         })
             .bind(web_port)
             .map_err(|e| logthrow!(e, e))
@@ -46,13 +52,19 @@ pub fn start() -> anyhow::Result<()> {
     let port: i32 = shared::libconfig::config::get("bind_port").expect("fail to load port from config");
     worker::run::<crate::server::explore::ExploreSharedChannel>(&(ip+":"+&port.to_string()),false);  
     super::channel::channel_service::start_up()?;
+    super::channel::plat_channel::start_up()?;
+    super::channel::battle_channel::start_up()?;
     worker::block_on(worker::get_shutdown_handler().recv())?;
     Ok(())
 }
 pub fn load_configs() -> anyhow::Result<()> {
     let dir: String = shared::libconfig::config::get("config_dir").expect("fail to load config dir");
+    //探索配置表先读取校验,全部通过后才替换
+    let tables = crate::config::ExploreTables::read(&dir).map_err(|e| logout!(e))?;
+    tables.validate()?;
     shared::libconfig::partner_config::load_config(&format!("{}/Partner.json", dir)).map_err(|e| logout!(e))?;
     shared::libconfig::common::load_config(&format!("{}/Common.json", dir)).map_err(|e| logout!(e))?;
+    tables.apply();
     Ok(())
 }
 #[allow(unused)]
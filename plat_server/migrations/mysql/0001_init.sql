@@ -0,0 +1,20 @@
+-- 平台服初始表结构
+CREATE TABLE IF NOT EXISTS db_player (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    account VARCHAR(64) NOT NULL,
+    name VARCHAR(64) NOT NULL DEFAULT '',
+    access_token VARCHAR(128) NOT NULL DEFAULT '',
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE KEY uk_account (account)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE TABLE IF NOT EXISTS db_player_character (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    player_id BIGINT UNSIGNED NOT NULL,
+    role_id INT UNSIGNED NOT NULL,
+    own_type INT NOT NULL DEFAULT 0,
+    state INT NOT NULL DEFAULT 1,
+    PRIMARY KEY (id),
+    KEY idx_player (player_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -0,0 +1,19 @@
+-- 玩家物品和探索结算
+CREATE TABLE IF NOT EXISTS db_player_item (
+    player_id BIGINT UNSIGNED NOT NULL,
+    item_id INT UNSIGNED NOT NULL,
+    `count` INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (player_id, item_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- 未通知玩家的结算在下次登录时下发
+CREATE TABLE IF NOT EXISTS db_explore_result (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    player_id BIGINT UNSIGNED NOT NULL,
+    result INT NOT NULL,
+    rewards TEXT NOT NULL,
+    delivered INT NOT NULL DEFAULT 0,
+    create_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    KEY idx_player_delivered (player_id, delivered)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
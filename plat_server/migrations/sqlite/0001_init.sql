@@ -0,0 +1,17 @@
+-- 平台服初始表结构
+CREATE TABLE IF NOT EXISTS db_player (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL DEFAULT '',
+    access_token TEXT NOT NULL DEFAULT '',
+    create_time TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS db_player_character (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    player_id INTEGER NOT NULL,
+    role_id INTEGER NOT NULL,
+    own_type INTEGER NOT NULL DEFAULT 0,
+    state INTEGER NOT NULL DEFAULT 1
+);
+CREATE INDEX IF NOT EXISTS idx_player_character_player ON db_player_character (player_id);
@@ -0,0 +1,18 @@
+-- 玩家物品和探索结算
+CREATE TABLE IF NOT EXISTS db_player_item (
+    player_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    `count` INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (player_id, item_id)
+);
+
+-- 未通知玩家的结算在下次登录时下发
+CREATE TABLE IF NOT EXISTS db_explore_result (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    player_id INTEGER NOT NULL,
+    result INTEGER NOT NULL,
+    rewards TEXT NOT NULL,
+    delivered INTEGER NOT NULL DEFAULT 0,
+    create_time TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_explore_result_player ON db_explore_result (player_id, delivered);
@@ -0,0 +1,9 @@
+-- 已结算的探索,探索服重复同步结算时不重复发放奖励
+-- 不同探索服的探索uuid可能相同,按(探索uuid,玩家)去重
+CREATE TABLE IF NOT EXISTS db_explore_settled (
+    explore_uuid INTEGER NOT NULL,
+    player_id INTEGER NOT NULL,
+    result_id INTEGER NOT NULL DEFAULT 0,
+    create_time TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (explore_uuid, player_id)
+);
@@ -0,0 +1,31 @@
+//! 探索结算奖励配置
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use once_cell::sync::Lazy;
+///奖励物品
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RewardItem{
+    pub item_id: u32,
+    pub count: u32,
+}
+///探索结果对应的奖励
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExploreRewardConfig{
+    ///探索结果(ExploreResult)
+    pub result: i32,
+    pub items: Vec<RewardItem>,
+}
+static REWARD_CONFIGS: Lazy<RwLock<BTreeMap<i32, Vec<RewardItem>>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+///加载奖励配置
+pub fn load_config(path: &str) -> anyhow::Result<()>{
+    let content = std::fs::read_to_string(path).map_err(|e| logthrow!(e, e))?;
+    let list = serde_json::from_str::<Vec<ExploreRewardConfig>>(&content)?;
+    let configs = list.into_iter().map(|c| (c.result, c.items)).collect::<BTreeMap<_,_>>();
+    info!("load {} explore rewards from {}", configs.len(), path);
+    *REWARD_CONFIGS.write().unwrap() = configs;
+    Ok(())
+}
+///获取探索结果的奖励,没有配置时没有奖励
+pub fn get_rewards(result: i32) -> Vec<RewardItem>{
+    REWARD_CONFIGS.read().unwrap().get(&result).cloned().unwrap_or_default()
+}
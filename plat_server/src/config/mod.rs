@@ -0,0 +1,2 @@
+//! 平台服配置表
+pub mod explore_reward;
@@ -3,10 +3,13 @@
 #[macro_use]
 extern crate shared;
 #[macro_use]
+extern crate async_trait;
+#[macro_use]
 extern crate log;
 extern crate anyhow;
 mod server;
 mod player;
+mod config;
 mod msg_id;
 #[allow(unused)]
 fn start_up() -> Result<i32,i32>{
@@ -9,4 +9,22 @@ pub const LOGIN_SUCCESS: u16 = 3004;
 ///开启探索
 pub const CREATE_EXPLORE_REQ: u16 = 3005;
 ///开启探索回调
-pub const CREATE_EXPLORE_REQ_RESULT: u16 = 3006;
\ No newline at end of file
+pub const CREATE_EXPLORE_REQ_RESULT: u16 = 3006;
+///探索结算通知(已由 EXPLORE_SETTLE_SYNC 代替)
+pub const EXPLORE_END_SYNC: u16 = 3007;
+///放弃探索
+pub const ABANDON_EXPLORE_REQ: u16 = 3008;
+///平台服放弃探索请求(探索服通道消息,与探索服msg_id一致)
+pub const ES_ABANDON_EXPLORE_REQ: u16 = 2101;
+///请求探索服负载(探索服通道消息,与探索服msg_id一致)
+pub const ES_EXPLORE_LOAD_REQ: u16 = 2102;
+///探索服负载上报(探索服通道消息,json)
+pub const ES_EXPLORE_LOAD_SYNC: u16 = 2103;
+///探索服注册(探索服通道消息,json)
+pub const ES_EXPLORE_REGISTER_REQ: u16 = 2104;
+///平台服标识(探索服通道消息,json)
+pub const ES_PLAT_SERVER_SYNC: u16 = 2105;
+///探索结算(探索服通道消息,json)
+pub const ES_EXPLORE_SETTLE_SYNC: u16 = 2106;
+///探索结算通知,包含发放的物品(json)
+pub const EXPLORE_SETTLE_SYNC: u16 = 3009;
@@ -1,3 +1,4 @@
 mod player;
 mod player_db_handler;
+pub mod storage;
 pub use {player::*, player_db_handler::*};
\ No newline at end of file
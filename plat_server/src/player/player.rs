@@ -5,6 +5,8 @@ use std::collections::VecDeque;
 use shared::{Transporter, MsgSendHandler, proto::{self, PackBuffer}, SessionTransport, libconfig::config};
 
 use super::DbHandler;
+use crate::config::explore_reward::RewardItem;
+use protobuf::ProtobufEnum;
 
 #[derive(Debug,Copy, Clone, PartialEq, Eq)]
 pub struct ExploreReq{
@@ -24,15 +26,27 @@ pub struct CharacterLoader{
     pub own_type:i32,
     pub state: i32,
 }
+///探索结算
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ExploreSettlement{
+    ///结算记录id
+    pub id: u64,
+    pub player_id: u64,
+    ///探索结果(ExploreResult)
+    pub result: i32,
+    pub rewards: Vec<RewardItem>,
+}
 #[derive(Debug, Clone)]
 pub struct PlayerLoginInfo{
     pub player_id: u64,
     pub name: String,
     pub characters: Vec<CharacterLoader>,
+    ///离线期间未通知的探索结算
+    pub explore_results: Vec<ExploreSettlement>,
 }
 impl PlayerLoginInfo{
     pub fn new(player_id: u64, name: String, characters: Vec<CharacterLoader>) -> Self{
-        Self{player_id, name, characters}
+        Self{player_id, name, characters, explore_results: Default::default()}
     }
 }
 #[derive(Debug)]
@@ -129,15 +143,31 @@ impl Player{
                 else{
                     match msg.unpack::<proto::C2PMsgCreateExploreReq>(){
                         Ok(pack) => {
+                            let server = match crate::server::channel::explore_manager::select_server(self.player_id()) {
+                                Some(server) => server,
+                                None => {
+                                    warn!("player {} create_explore fail, no explore server available",self.get_name());
+                                    let mut create_req = proto::P2CMsgCreateExploreResp::new();
+                                    create_req.set_result(proto::CreateExploreReqResult::FAIL);
+                                    self.msg_handler.send(SessionTransport::new(
+                                        proto::proto_code::DEFAULT_MAIN_CODE,
+                                        crate::msg_id::CREATE_EXPLORE_REQ_RESULT, 
+                                        rpc,
+                                        Box::new(create_req))).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+                                    return Ok(());
+                                }
+                            };
                             let mut req = proto::Ps2EsMsgExploreReq::new();
                             let characters = shared::server::worker::block_on(DbHandler::load_characters(self.player_id))?;
                             req.set_characters(characters.iter().map(|info| info.role_id).collect());
-                            req.set_explore_id(1);
-                            req.set_plat_server_id(1);
+                            req.set_explore_id(pack.get_explore_id());
+                            req.set_plat_server_id(crate::server::channel::explore_manager::plat_server_id());
                             req.set_player_id(self.player_id());
-                            crate::server::channel::explore_manager::send_msg(SessionTransport::new(
+                            crate::server::channel::explore_manager::send_msg(server, SessionTransport::new(
                                 proto::proto_code::DEFAULT_MAIN_CODE,
                                 proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_REQ, 0, Box::new(req)))?;
+                            //发送成功后才绑定,避免发送失败的探索服占住玩家
+                            crate::server::channel::explore_manager::bind_player(self.player_id(), server);
                             self.operation_queue.push_back(PlayerOperation::CreateExplore(ExploreReq{rpc,time: chrono::Local::now().timestamp_millis()}));
 
                         },
@@ -147,6 +177,19 @@ impl Player{
                     }
                 }
             }
+            crate::msg_id::ABANDON_EXPLORE_REQ => {
+                //放弃结果通过探索结算通知玩家
+                info!("player {} abandon explore",self.get_name());
+                let mut req = proto::Ps2EsMsgExploreReq::new();
+                req.set_player_id(self.player_id());
+                req.set_plat_server_id(crate::server::channel::explore_manager::plat_server_id());
+                let server = crate::server::channel::explore_manager::player_server(self.player_id())
+                .or_else(crate::server::channel::explore_manager::least_loaded)
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotConnected))?;
+                crate::server::channel::explore_manager::send_msg(server, SessionTransport::new(
+                    proto::proto_code::DEFAULT_MAIN_CODE,
+                    crate::msg_id::ES_ABANDON_EXPLORE_REQ, 0, Box::new(req)))?;
+            }
             _ => {
                 let e = Err(std::io::Error::from(std::io::ErrorKind::ConnectionAborted));
                 e?;
@@ -155,7 +198,28 @@ impl Player{
         Ok(())
     }
 
-    pub(crate) fn on_explore_create_resp(&mut self, resp: proto::Es2PsMsgExploreResp) -> anyhow::Result<()> {
+    ///通知玩家探索结算,通知成功后标记为已下发
+    pub(crate) fn on_explore_settled(&mut self, settlement: ExploreSettlement) -> anyhow::Result<()> {
+        info!("player {} explore settled {:?}", self.player_id, settlement);
+        let mut sync = protobuf::well_known_types::StringValue::new();
+        sync.set_value(serde_json::to_string(&settlement)?);
+        self.msg_handler.send(SessionTransport::new(
+            proto::proto_code::DEFAULT_MAIN_CODE,
+            crate::msg_id::EXPLORE_SETTLE_SYNC, 
+            0,
+            Box::new(sync))).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        DbHandler::mark_explore_result_delivered(settlement.id)?;
+        Ok(())
+    }
+    ///下发离线期间的探索结算
+    pub(crate) fn flush_explore_results(&mut self) -> anyhow::Result<()> {
+        let results = self.player_info.as_mut().map(|info| std::mem::take(&mut info.explore_results)).unwrap_or_default();
+        for settlement in results {
+            self.on_explore_settled(settlement)?;
+        }
+        Ok(())
+    }
+    pub(crate) fn on_explore_create_resp(&mut self, server: u32, resp: proto::Es2PsMsgExploreResp) -> anyhow::Result<()> {
         info!("on_explore_create_resp, {:?}",resp);
         if let Some(op) =  self.operation_queue.iter().enumerate()
         .find(|op| match op.1 {
@@ -168,8 +232,11 @@ impl Player{
                     create_req.set_result(proto::CreateExploreReqResult::SUCCESS);
                     create_req.set_explore_uuid(resp.get_explore_uuid());
                     create_req.set_access_token(resp.access_token);
-                    create_req.set_server_ip(config::get_str("explore_server_ip").unwrap_or_default());
-                    create_req.set_server_port(config::get("explore_server_port").unwrap_or_default());
+                    //探索服未上报地址时使用本地配置
+                    let (ip, port) = crate::server::channel::explore_manager::server_address(server)
+                    .unwrap_or_else(|| (config::get_str("explore_server_ip").unwrap_or_default(), config::get("explore_server_port").unwrap_or_default()));
+                    create_req.set_server_ip(ip);
+                    create_req.set_server_port(port);
                 },
                 proto::ExploreCreateResult::FAIL => {
                     create_req.set_result(proto::CreateExploreReqResult::FAIL);
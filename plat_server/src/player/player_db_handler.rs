@@ -1,8 +1,8 @@
 use crossbeam::channel::*;
 use futures::FutureExt;
-use shared::{db::{DbCommand, DbResult, MergeDbResult}};
-use shared::libconfig;
+use shared::{db::{DbCommand, DbResult}};
 use crate::{player::*};
+use crate::config::explore_reward::RewardItem;
 #[derive(Default, Debug)]
 pub struct DbHandler{
     ///玩家登录消息
@@ -24,57 +24,58 @@ impl DbHandler{
     }
     ///加载角色列表
     pub async fn load_characters(player_id: u64) -> anyhow::Result<Vec<CharacterLoader>> {
-        let pool = shared::db::get_pool("bg_db_server")?;
-        let characters = sqlx::query_as::<_,CharacterLoader>("SELECT id,role_id,own_type,state FROM db_player_character WHERE player_id=?")
-        .bind(player_id)
-        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
-        Ok(characters)
+        let start = std::time::Instant::now();
+        let ret = super::storage::get().load_characters(player_id).await;
+        crate::server::metrics::observe_db("load_characters", start.elapsed());
+        ret
     }
     ///load player, if not exist, create player
     async fn on_load_player(account: &str, access_token: &str,mut cmd: DbCommand<PlayerLoginInfo>) -> DbResult<PlayerLoginInfo>{
-        let pool = shared::db::get_pool("bg_db_server").merge_to(&cmd)?;
-        let result = sqlx::query_as::<_,(u64,String,String)>("SELECT id,name,access_token FROM db_player WHERE account=?")
-        .bind(&account)
-        .fetch_optional(pool.as_ref()).await.merge_to(&cmd).map_err(|e| logthrow!(e,e))?;
-        if let Some((player_id,name,token)) = result{
-            if access_token != &token{
-                warn!("player {} token invalid expected {}, got {}",account,token, access_token);
-                cmd.to_err("token校验失败".to_string());
-                return Err(cmd);
+        let start = std::time::Instant::now();
+        let player = super::storage::get().on_load_player(account, access_token).await;
+        crate::server::metrics::observe_db("load_player", start.elapsed());
+        match player{
+            Ok(mut player) => {
+                let start = std::time::Instant::now();
+                let explore_results = super::storage::get().load_explore_results(player.player_id).await;
+                crate::server::metrics::observe_db("load_explore_results", start.elapsed());
+                player.explore_results = explore_results
+                .map_err(|e| error!("fail to load explore results of player {}, {:?}", player.player_id, e))
+                .unwrap_or_default();
+                info!("player [{}] load success, name {}, characters :{:?}",account,player.name,player.characters);
+                cmd.set(player);
+                Ok(cmd)
+            },
+            Err(e) => {
+                cmd.to_err(e.to_string());
+                Err(cmd)
             }
-            let mut characters = sqlx::query_as::<_,CharacterLoader>("SELECT id,role_id,own_type,state FROM db_player_character WHERE player_id=?")
-            .bind(player_id)
-            .fetch_all(pool.as_ref()).await.merge_to(&cmd).map_err(|e| logthrow!(e,e))?;
-            if characters.len() == 0{
-                let default_characters = if let Some(r) = libconfig::common::get_str("InitialRole"){
-                    let vs = r.split("|").collect::<Vec<_>>();
-                    vs.iter().filter_map(|s| s.parse::<u32>().ok()).collect::<Vec<_>>()
-                }
-                else{
-                    vec![10111,10211,10311,10411]
-                };
-                for cid in default_characters {
-                    let query = sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,0,1)")
-                    .bind(cid).bind(player_id)
-                    .execute(pool.as_ref()).await.merge_to(&cmd).map_err(|e| logthrow!(e,e))?;
-                    characters.push(CharacterLoader{
-                        id: query.last_insert_id(),
-                        role_id: cid,
-                        own_type: 0,
-                        state: 1
-                    });
-                }
-            }
-            info!("player [{}] load success, name {}, characters :{:?}",account,name,characters);
-            cmd.set(PlayerLoginInfo::new(player_id, name, characters));
-            Ok(cmd)
-        }
-        else{
-            warn!("player {} load fail (account not exist)", account);
-            cmd.to_err("没有这个玩家".to_string());
-            Err(cmd)
         }
     }
+    ///发放探索奖励,保存成功后回调结算信息,已结算过的探索忽略
+    pub fn settle_explore(player_id: u64, explore_uuid: u64, result: i32, rewards: Vec<RewardItem>, callback: impl FnOnce(ExploreSettlement) + Send + 'static) -> anyhow::Result<()>{
+        shared::db::send_query(Box::new(async move {
+            let start = std::time::Instant::now();
+            let ret = super::storage::get().settle_explore(player_id, explore_uuid, result, &rewards).await;
+            crate::server::metrics::observe_db("settle_explore", start.elapsed());
+            match ret{
+                Ok(Some(id)) => callback(ExploreSettlement{ id, player_id, result, rewards }),
+                Ok(None) => info!("explore {} of player {} already settled", explore_uuid, player_id),
+                Err(e) => error!("fail to settle explore {:?} of player {}, {:?}", (result, &rewards), player_id, e),
+            }
+        }).boxed())?;
+        Ok(())
+    }
+    ///标记探索结算已下发
+    pub fn mark_explore_result_delivered(id: u64) -> anyhow::Result<()>{
+        shared::db::send_query(Box::new(async move {
+            let start = std::time::Instant::now();
+            let ret = super::storage::get().mark_explore_result_delivered(id).await;
+            crate::server::metrics::observe_db("mark_explore_result_delivered", start.elapsed());
+            ret.map_err(|e| error!("fail to mark explore result {} delivered, {:?}", id, e)).ok();
+        }).boxed())?;
+        Ok(())
+    }
     ///尝试获取玩家的查询结果,如果结果还未查到,返回Err(())
     pub fn try_get_player_info(&self) -> Result<DbResult<PlayerLoginInfo>,()>{
         match &self.player_info_handler{
@@ -0,0 +1,111 @@
+//! 内存存储,不做持久化,用于测试
+//!
+//! 与mysql一致,账号不存在时登录失败,账号需通过 add_player 预先添加
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use super::super::{CharacterLoader, ExploreSettlement, PlayerLoginInfo};
+use crate::config::explore_reward::RewardItem;
+use super::PlayerStorage;
+#[derive(Default)]
+struct MemoryData{
+    next_id: u64,
+    ///账号 -> (玩家id, 名字, token)
+    players: BTreeMap<String, (u64, String, String)>,
+    characters: BTreeMap<u64, Vec<CharacterLoader>>,
+    ///(玩家id, 物品id) -> 数量
+    items: BTreeMap<(u64, u32), u32>,
+    ///未下发的结算
+    explore_results: BTreeMap<u64, ExploreSettlement>,
+    ///已结算的探索(探索uuid, 玩家id)
+    settled_explores: BTreeSet<(u64, u64)>,
+}
+#[derive(Default)]
+pub struct MemoryStorage{
+    data: Mutex<MemoryData>,
+}
+impl MemoryStorage{
+    ///添加账号,返回玩家id
+    pub fn add_player(&self, account: &str, name: &str, access_token: &str) -> u64{
+        let mut data = self.data.lock().unwrap();
+        data.next_id += 1;
+        let player_id = data.next_id;
+        data.players.insert(account.to_string(), (player_id, name.to_string(), access_token.to_string()));
+        player_id
+    }
+}
+#[async_trait]
+impl PlayerStorage for MemoryStorage{
+    async fn on_load_player(&self, account: &str, access_token: &str) -> anyhow::Result<PlayerLoginInfo>{
+        let mut data = self.data.lock().unwrap();
+        let (player_id, name, token) = match data.players.get(account) {
+            Some(player) => player.clone(),
+            None => {
+                warn!("player {} load fail (account not exist)", account);
+                return Err(anyhow::anyhow!("没有这个玩家"));
+            }
+        };
+        if access_token != &token{
+            warn!("player {} token invalid expected {}, got {}",account,token, access_token);
+            return Err(anyhow::anyhow!("token校验失败"));
+        }
+        let MemoryData{ next_id, characters, .. } = &mut *data;
+        let characters = characters.entry(player_id).or_default();
+        if characters.len() == 0{
+            for cid in super::default_characters() {
+                *next_id += 1;
+                characters.push(CharacterLoader{
+                    id: *next_id,
+                    role_id: cid,
+                    own_type: 0,
+                    state: 1
+                });
+            }
+        }
+        Ok(PlayerLoginInfo::new(player_id, name, characters.clone()))
+    }
+    async fn load_characters(&self, player_id: u64) -> anyhow::Result<Vec<CharacterLoader>>{
+        Ok(self.data.lock().unwrap().characters.get(&player_id).cloned().unwrap_or_default())
+    }
+    async fn settle_explore(&self, player_id: u64, explore_uuid: u64, result: i32, rewards: &[RewardItem]) -> anyhow::Result<Option<u64>>{
+        let mut data = self.data.lock().unwrap();
+        if !data.settled_explores.insert((explore_uuid, player_id)){
+            return Ok(None);
+        }
+        for item in rewards {
+            *data.items.entry((player_id, item.item_id)).or_default() += item.count;
+        }
+        data.next_id += 1;
+        let id = data.next_id;
+        data.explore_results.insert(id, ExploreSettlement{ id, player_id, result, rewards: rewards.to_vec() });
+        Ok(Some(id))
+    }
+    async fn load_explore_results(&self, player_id: u64) -> anyhow::Result<Vec<ExploreSettlement>>{
+        Ok(self.data.lock().unwrap().explore_results.values().filter(|r| r.player_id == player_id).cloned().collect())
+    }
+    async fn mark_explore_result_delivered(&self, id: u64) -> anyhow::Result<()>{
+        self.data.lock().unwrap().explore_results.remove(&id);
+        Ok(())
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_memory_account(){
+    let storage = MemoryStorage::default();
+    assert!(futures::executor::block_on(storage.on_load_player("account", "token")).is_err());
+    let player_id = storage.add_player("account", "name", "token");
+    assert!(futures::executor::block_on(storage.on_load_player("account", "bad")).is_err());
+    let player = futures::executor::block_on(storage.on_load_player("account", "token")).unwrap();
+    assert_eq!(player.player_id, player_id);
+}
+#[cfg(test)]
+#[test]
+fn test_memory_settle_once(){
+    let storage = MemoryStorage::default();
+    let rewards = [RewardItem{ item_id: 5001, count: 2 }];
+    assert!(futures::executor::block_on(storage.settle_explore(1, 100, 3, &rewards)).unwrap().is_some());
+    assert!(futures::executor::block_on(storage.settle_explore(1, 100, 3, &rewards)).unwrap().is_none());
+    //其他探索服相同uuid的探索照常结算
+    assert!(futures::executor::block_on(storage.settle_explore(2, 100, 3, &rewards)).unwrap().is_some());
+    assert_eq!(storage.data.lock().unwrap().items.get(&(1, 5001)), Some(&2));
+    assert_eq!(futures::executor::block_on(storage.load_explore_results(1)).unwrap().len(), 1);
+}
@@ -0,0 +1,39 @@
+//! 数据库版本迁移
+//!
+//! 已执行的版本记录在 plat_schema_version 表中,与探索服的版本表互不影响
+//! 平台服负责创建玩家相关的表,包括探索服写入的 db_player_character
+///版本记录表
+pub const VERSION_TABLE: &str = "plat_schema_version";
+pub use db_migration::{Migration, pending};
+pub static MYSQL_MIGRATIONS: &[Migration] = &[
+    Migration{ version: 1, name: "init", sql: include_str!("../../../migrations/mysql/0001_init.sql") },
+    Migration{ version: 2, name: "explore_result", sql: include_str!("../../../migrations/mysql/0002_explore_result.sql") },
+    Migration{ version: 3, name: "explore_settled", sql: include_str!("../../../migrations/mysql/0003_explore_settled.sql") },
+];
+pub static SQLITE_MIGRATIONS: &[Migration] = &[
+    Migration{ version: 1, name: "init", sql: include_str!("../../../migrations/sqlite/0001_init.sql") },
+    Migration{ version: 2, name: "explore_result", sql: include_str!("../../../migrations/sqlite/0002_explore_result.sql") },
+    Migration{ version: 3, name: "explore_settled", sql: include_str!("../../../migrations/sqlite/0003_explore_settled.sql") },
+];
+///版本记录表建表语句
+#[inline]
+pub fn version_table_sql() -> String{
+    db_migration::version_table_sql(VERSION_TABLE)
+}
+///当前版本查询语句
+#[inline]
+pub fn current_version_sql() -> String{
+    db_migration::current_version_sql(VERSION_TABLE)
+}
+///记录已执行版本的语句
+#[inline]
+pub fn insert_version_sql() -> String{
+    db_migration::insert_version_sql(VERSION_TABLE)
+}
+#[cfg(test)]
+#[test]
+fn test_migration_order(){
+    assert!(db_migration::check(MYSQL_MIGRATIONS) && db_migration::check(SQLITE_MIGRATIONS));
+    assert_eq!(MYSQL_MIGRATIONS.len(), SQLITE_MIGRATIONS.len());
+    assert!(MYSQL_MIGRATIONS.iter().zip(SQLITE_MIGRATIONS).all(|(m, s)| m.version == s.version && m.name == s.name));
+}
@@ -0,0 +1,59 @@
+//! 玩家数据存储
+//!
+//! 通过配置项 storage 选择存储方式: mysql(默认), sqlite, memory
+mod mysql;
+mod sqlite;
+mod memory;
+pub mod migration;
+use once_cell::sync::OnceCell;
+use shared::libconfig;
+use super::{CharacterLoader, ExploreSettlement, PlayerLoginInfo};
+use crate::config::explore_reward::RewardItem;
+pub use {mysql::MySqlStorage, sqlite::SqliteStorage, memory::MemoryStorage};
+#[async_trait]
+pub trait PlayerStorage: Send + Sync{
+    ///执行数据库迁移,内存存储无需迁移
+    async fn migrate(&self) -> anyhow::Result<()>{
+        Ok(())
+    }
+    ///加载玩家并校验token,没有角色时创建默认角色
+    async fn on_load_player(&self, account: &str, access_token: &str) -> anyhow::Result<PlayerLoginInfo>;
+    ///加载角色列表
+    async fn load_characters(&self, player_id: u64) -> anyhow::Result<Vec<CharacterLoader>>;
+    ///发放探索奖励并记录未下发的结算,返回结算记录id,探索已结算过时返回None
+    async fn settle_explore(&self, player_id: u64, explore_uuid: u64, result: i32, rewards: &[RewardItem]) -> anyhow::Result<Option<u64>>;
+    ///加载未下发的探索结算
+    async fn load_explore_results(&self, player_id: u64) -> anyhow::Result<Vec<ExploreSettlement>>;
+    ///标记结算已下发
+    async fn mark_explore_result_delivered(&self, id: u64) -> anyhow::Result<()>;
+}
+static STORAGE: OnceCell<Box<dyn PlayerStorage>> = OnceCell::new();
+///根据配置初始化存储并执行数据库迁移
+pub fn init() -> anyhow::Result<()>{
+    let storage: Box<dyn PlayerStorage> = match libconfig::config::get_str("storage").unwrap_or_default().as_str() {
+        "memory" => Box::new(MemoryStorage::default()),
+        "sqlite" => Box::new(SqliteStorage::new(&libconfig::config::get_str("sqlite_db").expect("fail to load sqlite_db"))?),
+        _ => Box::new(MySqlStorage::new(&libconfig::config::get_str("player_db").expect("fail to load db_path"))),
+    };
+    shared::server::worker::block_on(storage.migrate())?;
+    set(storage)
+}
+///设置存储,只能设置一次
+pub fn set(storage: Box<dyn PlayerStorage>) -> anyhow::Result<()>{
+    STORAGE.set(storage).map_err(|_| std::io::Error::from(std::io::ErrorKind::AlreadyExists))?;
+    Ok(())
+}
+#[inline]
+pub fn get() -> &'static dyn PlayerStorage{
+    STORAGE.get().expect("player storage not initialized").as_ref()
+}
+///新玩家的初始角色
+fn default_characters() -> Vec<u32>{
+    if let Some(r) = libconfig::common::get_str("InitialRole"){
+        let vs = r.split("|").collect::<Vec<_>>();
+        vs.iter().filter_map(|s| s.parse::<u32>().ok()).collect::<Vec<_>>()
+    }
+    else{
+        vec![10111,10211,10311,10411]
+    }
+}
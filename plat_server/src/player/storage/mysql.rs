@@ -0,0 +1,117 @@
+//! mysql存储
+use super::super::{CharacterLoader, ExploreSettlement, PlayerLoginInfo};
+use crate::config::explore_reward::RewardItem;
+use super::{PlayerStorage, migration};
+///数据库连接池名
+pub const POOL_NAME: &str = "bg_db_server";
+pub struct MySqlStorage;
+impl MySqlStorage{
+    ///启动连接池
+    pub fn new(db_path: &str) -> Self{
+        shared::db::start_pools(vec![shared::db::DbPoolInfo{db_path: db_path.to_string(), db_name: POOL_NAME.to_string(),max_conn: 20}]);
+        Self
+    }
+}
+#[async_trait]
+impl PlayerStorage for MySqlStorage{
+    async fn migrate(&self) -> anyhow::Result<()>{
+        use sqlx::Executor;
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        pool.as_ref().execute(migration::version_table_sql().as_str()).await.map_err(|e| logthrow!(e,e))?;
+        let current = sqlx::query_as::<_,(Option<i64>,)>(&migration::current_version_sql())
+        .fetch_one(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?.0;
+        for m in migration::pending(migration::MYSQL_MIGRATIONS, current) {
+            info!("apply migration {} {} to {}", m.version, m.name, POOL_NAME);
+            //mysql的DDL会隐式提交,事务只保证数据变更与版本记录一起提交,迁移脚本仍需可重复执行
+            let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
+            (&mut trans).execute(m.sql).await.map_err(|e| logthrow!(e,e))?;
+            sqlx::query(&migration::insert_version_sql())
+            .bind(m.version).bind(m.name).bind(chrono::Local::now().timestamp_millis())
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+            trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_load_player(&self, account: &str, access_token: &str) -> anyhow::Result<PlayerLoginInfo>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let result = sqlx::query_as::<_,(u64,String,String)>("SELECT id,name,access_token FROM db_player WHERE account=?")
+        .bind(&account)
+        .fetch_optional(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        let (player_id,name,token) = match result{
+            Some(player) => player,
+            None => {
+                warn!("player {} load fail (account not exist)", account);
+                return Err(anyhow::anyhow!("没有这个玩家"));
+            }
+        };
+        if access_token != &token{
+            warn!("player {} token invalid expected {}, got {}",account,token, access_token);
+            return Err(anyhow::anyhow!("token校验失败"));
+        }
+        let mut characters = sqlx::query_as::<_,CharacterLoader>("SELECT id,role_id,own_type,state FROM db_player_character WHERE player_id=?")
+        .bind(player_id)
+        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        if characters.len() == 0{
+            for cid in super::default_characters() {
+                let query = sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,0,1)")
+                .bind(cid).bind(player_id)
+                .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+                characters.push(CharacterLoader{
+                    id: query.last_insert_id(),
+                    role_id: cid,
+                    own_type: 0,
+                    state: 1
+                });
+            }
+        }
+        Ok(PlayerLoginInfo::new(player_id, name, characters))
+    }
+    async fn load_characters(&self, player_id: u64) -> anyhow::Result<Vec<CharacterLoader>>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let characters = sqlx::query_as::<_,CharacterLoader>("SELECT id,role_id,own_type,state FROM db_player_character WHERE player_id=?")
+        .bind(player_id)
+        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        Ok(characters)
+    }
+    async fn settle_explore(&self, player_id: u64, explore_uuid: u64, result: i32, rewards: &[RewardItem]) -> anyhow::Result<Option<u64>>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let mut trans = pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        let settled = sqlx::query("INSERT IGNORE INTO db_explore_settled (explore_uuid,player_id) VALUES(?,?)")
+        .bind(explore_uuid).bind(player_id)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        if settled.rows_affected() == 0{
+            trans.rollback().await.map_err(|e| logthrow!(e,e))?;
+            return Ok(None);
+        }
+        for item in rewards {
+            sqlx::query("INSERT INTO db_player_item (player_id,item_id,`count`) VALUES(?,?,?) ON DUPLICATE KEY UPDATE `count`=`count`+VALUES(`count`)")
+            .bind(player_id).bind(item.item_id).bind(item.count)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        let query = sqlx::query("INSERT INTO db_explore_result (player_id,result,rewards) VALUES(?,?,?)")
+        .bind(player_id).bind(result).bind(serde_json::to_string(rewards)?)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        sqlx::query("UPDATE db_explore_settled SET result_id=? WHERE explore_uuid=? AND player_id=?")
+        .bind(query.last_insert_id()).bind(explore_uuid).bind(player_id)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(Some(query.last_insert_id()))
+    }
+    async fn load_explore_results(&self, player_id: u64) -> anyhow::Result<Vec<ExploreSettlement>>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        let results = sqlx::query_as::<_,(u64,i32,String)>("SELECT id,result,rewards FROM db_explore_result WHERE player_id=? AND delivered=0 ORDER BY id")
+        .bind(player_id)
+        .fetch_all(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        Ok(results.into_iter().map(|(id, result, rewards)| ExploreSettlement{
+            id, player_id, result,
+            rewards: serde_json::from_str(&rewards).unwrap_or_default(),
+        }).collect())
+    }
+    async fn mark_explore_result_delivered(&self, id: u64) -> anyhow::Result<()>{
+        let pool = shared::db::get_pool(POOL_NAME)?;
+        sqlx::query("UPDATE db_explore_result SET delivered=1 WHERE id=?")
+        .bind(id)
+        .execute(pool.as_ref()).await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+}
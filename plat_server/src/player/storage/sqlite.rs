@@ -0,0 +1,149 @@
+//! sqlite存储,用于单机部署和测试
+use std::str::FromStr;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use super::super::{CharacterLoader, ExploreSettlement, PlayerLoginInfo};
+use crate::config::explore_reward::RewardItem;
+use super::{PlayerStorage, migration};
+pub struct SqliteStorage{
+    pool: SqlitePool,
+}
+impl SqliteStorage{
+    pub fn new(db_path: &str) -> anyhow::Result<Self>{
+        let options = SqliteConnectOptions::from_str(db_path)?.create_if_missing(true);
+        Ok(Self{
+            pool: SqlitePoolOptions::new().max_connections(4).connect_lazy_with(options),
+        })
+    }
+    #[inline]
+    pub fn pool(&self) -> &SqlitePool{
+        &self.pool
+    }
+}
+#[async_trait]
+impl PlayerStorage for SqliteStorage{
+    async fn migrate(&self) -> anyhow::Result<()>{
+        use sqlx::Executor;
+        self.pool.execute(migration::version_table_sql().as_str()).await.map_err(|e| logthrow!(e,e))?;
+        let current = sqlx::query_as::<_,(Option<i64>,)>(&migration::current_version_sql())
+        .fetch_one(&self.pool).await.map_err(|e| logthrow!(e,e))?.0;
+        for m in migration::pending(migration::SQLITE_MIGRATIONS, current) {
+            info!("apply migration {} {}", m.version, m.name);
+            let mut trans = self.pool.begin().await.map_err(|e| logthrow!(e,e))?;
+            (&mut trans).execute(m.sql).await.map_err(|e| logthrow!(e,e))?;
+            sqlx::query(&migration::insert_version_sql())
+            .bind(m.version).bind(m.name).bind(chrono::Local::now().timestamp_millis())
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+            trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        }
+        Ok(())
+    }
+    async fn on_load_player(&self, account: &str, access_token: &str) -> anyhow::Result<PlayerLoginInfo>{
+        let result = sqlx::query_as::<_,(i64,String,String)>("SELECT id,name,access_token FROM db_player WHERE account=?")
+        .bind(&account)
+        .fetch_optional(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        let (player_id,name,token) = match result{
+            Some((player_id,name,token)) => (player_id as u64,name,token),
+            None => {
+                warn!("player {} load fail (account not exist)", account);
+                return Err(anyhow::anyhow!("没有这个玩家"));
+            }
+        };
+        if access_token != &token{
+            warn!("player {} token invalid expected {}, got {}",account,token, access_token);
+            return Err(anyhow::anyhow!("token校验失败"));
+        }
+        let mut characters = self.load_characters(player_id).await?;
+        if characters.len() == 0{
+            for cid in super::default_characters() {
+                let query = sqlx::query("INSERT INTO db_player_character (role_id,player_id,own_type,state) VALUES(?,?,0,1)")
+                .bind(cid).bind(player_id as i64)
+                .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+                characters.push(CharacterLoader{
+                    id: query.last_insert_rowid() as u64,
+                    role_id: cid,
+                    own_type: 0,
+                    state: 1
+                });
+            }
+        }
+        Ok(PlayerLoginInfo::new(player_id, name, characters))
+    }
+    async fn load_characters(&self, player_id: u64) -> anyhow::Result<Vec<CharacterLoader>>{
+        let characters = sqlx::query_as::<_,(i64,i64,i32,i32)>("SELECT id,role_id,own_type,state FROM db_player_character WHERE player_id=?")
+        .bind(player_id as i64)
+        .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?
+        .into_iter()
+        .map(|(id, role_id, own_type, state)| CharacterLoader{ id: id as u64, role_id: role_id as u32, own_type, state })
+        .collect();
+        Ok(characters)
+    }
+    async fn settle_explore(&self, player_id: u64, explore_uuid: u64, result: i32, rewards: &[RewardItem]) -> anyhow::Result<Option<u64>>{
+        let mut trans = self.pool.begin().await.map_err(|e| logthrow!(e,e))?;
+        let settled = sqlx::query("INSERT OR IGNORE INTO db_explore_settled (explore_uuid,player_id) VALUES(?,?)")
+        .bind(explore_uuid as i64).bind(player_id as i64)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        if settled.rows_affected() == 0{
+            trans.rollback().await.map_err(|e| logthrow!(e,e))?;
+            return Ok(None);
+        }
+        for item in rewards {
+            sqlx::query("INSERT INTO db_player_item (player_id,item_id,`count`) VALUES(?,?,?) ON CONFLICT(player_id,item_id) DO UPDATE SET `count`=`count`+excluded.`count`")
+            .bind(player_id as i64).bind(item.item_id).bind(item.count)
+            .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        }
+        let query = sqlx::query("INSERT INTO db_explore_result (player_id,result,rewards) VALUES(?,?,?)")
+        .bind(player_id as i64).bind(result).bind(serde_json::to_string(rewards)?)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        sqlx::query("UPDATE db_explore_settled SET result_id=? WHERE explore_uuid=? AND player_id=?")
+        .bind(query.last_insert_rowid()).bind(explore_uuid as i64).bind(player_id as i64)
+        .execute(&mut trans).await.map_err(|e| logthrow!(e,e))?;
+        trans.commit().await.map_err(|e| logthrow!(e,e))?;
+        Ok(Some(query.last_insert_rowid() as u64))
+    }
+    async fn load_explore_results(&self, player_id: u64) -> anyhow::Result<Vec<ExploreSettlement>>{
+        let results = sqlx::query_as::<_,(i64,i32,String)>("SELECT id,result,rewards FROM db_explore_result WHERE player_id=? AND delivered=0 ORDER BY id")
+        .bind(player_id as i64)
+        .fetch_all(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        Ok(results.into_iter().map(|(id, result, rewards)| ExploreSettlement{
+            id: id as u64, player_id, result,
+            rewards: serde_json::from_str(&rewards).unwrap_or_default(),
+        }).collect())
+    }
+    async fn mark_explore_result_delivered(&self, id: u64) -> anyhow::Result<()>{
+        sqlx::query("UPDATE db_explore_result SET delivered=1 WHERE id=?")
+        .bind(id as i64)
+        .execute(&self.pool).await.map_err(|e| logthrow!(e,e))?;
+        Ok(())
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_sqlite_migrate(){
+    let path = std::env::temp_dir().join(format!("plat_storage_{}.db", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(async {
+        let storage = SqliteStorage::new(&format!("sqlite://{}", path.display())).unwrap();
+        storage.migrate().await.unwrap();
+        //重复迁移不会重复执行脚本
+        storage.migrate().await.unwrap();
+        let current = sqlx::query_as::<_,(Option<i64>,)>(&migration::current_version_sql())
+        .fetch_one(storage.pool()).await.unwrap().0;
+        assert_eq!(current, migration::SQLITE_MIGRATIONS.last().map(|m| m.version));
+        let count = sqlx::query_as::<_,(i64,)>(&format!("SELECT COUNT(*) FROM {}", migration::VERSION_TABLE))
+        .fetch_one(storage.pool()).await.unwrap().0;
+        assert_eq!(count as usize, migration::SQLITE_MIGRATIONS.len());
+        sqlx::query("SELECT id,role_id,own_type,state FROM db_player_character").fetch_all(storage.pool()).await.unwrap();
+        //同一探索只结算一次
+        let rewards = [RewardItem{ item_id: 5001, count: 2 }];
+        assert!(storage.settle_explore(1, 100, 3, &rewards).await.unwrap().is_some());
+        assert!(storage.settle_explore(1, 100, 3, &rewards).await.unwrap().is_none());
+        //其他探索服相同uuid的探索照常结算
+        assert!(storage.settle_explore(2, 100, 3, &rewards).await.unwrap().is_some());
+        let count = sqlx::query_as::<_,(i64,)>("SELECT `count` FROM db_player_item WHERE player_id=1 AND item_id=5001")
+        .fetch_one(storage.pool()).await.unwrap().0;
+        assert_eq!(count, 2);
+        assert_eq!(storage.load_explore_results(1).await.unwrap().len(), 1);
+    });
+    std::fs::remove_file(&path).ok();
+}
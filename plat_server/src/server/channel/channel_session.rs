@@ -1,14 +1,46 @@
 
-use shared::{SyncSessionHandler, proto::PackBuffer, server::{channel::{ChannelState, ServiceChannel}}, timer::IntervalTimer};
+use crossbeam::channel::Receiver;
+use protobuf::well_known_types::StringValue;
+use shared::{SessionTransport, SyncSessionHandler, proto::PackBuffer, server::{channel::{ChannelState, ServiceChannel}}, timer::IntervalTimer};
+use super::{explore_channel::ExploreLoad, explore_manager};
 ///本地服务远程会话
 pub struct ChannelSession{
     handler: SyncSessionHandler<()>,
     state: ChannelState,
     heart_timer: IntervalTimer,
     channele_type: shared::proto::ChannelClientType,
+    ///注册的探索服id
+    server_id: Option<u32>,
+    ///发往注册探索服的消息
+    outbound: Option<Receiver<SessionTransport<()>>>,
 }
 
 impl ChannelSession{
+    fn unpack_load(packet: &PackBuffer) -> anyhow::Result<ExploreLoad>{
+        let pack = packet.unpack::<StringValue>().map_err(|_| shared::error::unpack_err())?;
+        Ok(serde_json::from_str(pack.get_value()).map_err(|e| logthrow!(e, e))?)
+    }
+    ///探索服注册
+    fn on_register(&mut self, packet: &PackBuffer) -> anyhow::Result<()>{
+        if self.channele_type != shared::proto::ChannelClientType::ExploreServer {
+            warn!("channel {} of {:?} try to register as explore server", self.handler.id(), self.channele_type);
+            return shared::error::any_err(std::io::ErrorKind::PermissionDenied);
+        }
+        let load = Self::unpack_load(packet)?;
+        let (tx, rx) = crossbeam::channel::unbounded();
+        self.server_id = Some(load.server_id);
+        self.outbound = Some(rx);
+        explore_manager::register(self.handler.id(), load, tx);
+        self.send_msg(explore_manager::plat_identity()?).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        Ok(())
+    }
+    ///连接断开,注销探索服
+    fn release_server(&mut self){
+        if self.server_id.take().is_some() {
+            explore_manager::unregister(self.handler.id());
+        }
+        self.outbound = None;
+    }
 }
 impl ServiceChannel<()> for ChannelSession{
     #[inline]
@@ -30,20 +62,38 @@ impl ServiceChannel<()> for ChannelSession{
             heart_timer: IntervalTimer::new(30*1000),
             state,
             channele_type: shared::proto::ChannelClientType::UnDefined,
+            server_id: None,
+            outbound: None,
         }
     }
     fn update(&mut self, diff: i64){
+        if self.state == ChannelState::Disconnected {
+            self.release_server();
+            return;
+        }
         self.handle_event();
+        if let Some(outbound) = self.outbound.as_ref() {
+            let msgs = outbound.try_iter().collect::<Vec<_>>();
+            for msg in msgs {
+                self.send_msg(msg).map_err(|_| error!("fail to send msg to explore server {:?}", self.server_id)).ok();
+            }
+        }
         self.heart_timer.update(diff);
         if self.heart_timer.passed(){
             self.state = ChannelState::Disconnected;
             self.disconnect().ok();
+            self.release_server();
             info!("service channel {} disconnected",self.handler.id());
         }
     }
 
-    fn on_packet(&mut self, _packet: PackBuffer)-> anyhow::Result<()> {
-        //TODO
+    fn on_packet(&mut self, packet: PackBuffer)-> anyhow::Result<()> {
+        match (packet.header().sub_code() as u16, self.server_id) {
+            (crate::msg_id::ES_EXPLORE_REGISTER_REQ, _) => self.on_register(&packet)?,
+            (crate::msg_id::ES_EXPLORE_LOAD_SYNC, Some(_)) => explore_manager::on_load(self.handler.id(), Self::unpack_load(&packet)?),
+            (_, Some(server_id)) => explore_manager::on_explore_msg(server_id, packet)?,
+            (opcode, None) => warn!("unexpected opcode {} from channel {:?}", opcode, self.channele_type),
+        }
         Ok(())
     }
 
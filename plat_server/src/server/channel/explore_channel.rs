@@ -4,19 +4,50 @@ use futures::FutureExt;
 use shared::{SyncSessionHandler, server::{channel::{self, ServiceChannel, ChannelState}}, timer::IntervalTimer};
 
 use crate::server::world::WorldCommand;
+///探索服负载信息,由探索服在心跳时上报
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct ExploreLoad{
+    pub server_id: u32,
+    ///进行中的探索数量
+    pub bearing: usize,
+    ///玩家连接探索服的地址
+    pub server_ip: String,
+    pub server_port: i32,
+    ///最大探索数量,0为不限制
+    #[serde(default)]
+    pub capacity: usize,
+}
+impl ExploreLoad{
+    ///是否还能分配新探索
+    #[inline]
+    pub fn available(&self) -> bool{
+        self.capacity == 0 || self.bearing < self.capacity
+    }
+}
+///探索结算,由探索服在探索结束时同步
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExploreSettleSync{
+    pub player_id: u64,
+    ///探索id,同一探索只结算一次
+    pub explore_uuid: u64,
+    ///探索结果(ExploreResult)
+    pub result: i32,
+}
 pub struct ExploreChannel{
     handler: SyncSessionHandler<()>,
     heart_timer: IntervalTimer,
     state: ChannelState,
+    addr: String,
+    load: ExploreLoad,
+    ///收到新的负载上报
+    load_updated: bool,
     reconnect_handler: crossbeam::channel::Receiver<SyncSessionHandler<()>>,
     reconnect_callback: crossbeam::channel::Sender<SyncSessionHandler<()>>,
     msg_handler: crossbeam::channel::Sender<WorldCommand>,
 }
 impl ExploreChannel{
     ///启动服务
-    pub(crate) fn start_up(msg_handler: crossbeam::channel::Sender<WorldCommand>, mut try_times: i32) -> anyhow::Result<Self>{
-        let addr = format!("{}:{}", shared::libconfig::config::get_str("explore_channel_ip").unwrap(),
-        shared::libconfig::config::get::<i32>("explore_channel_port").expect("config explore_channel_port expected"));
+    pub(crate) fn start_up(addr: String, msg_handler: crossbeam::channel::Sender<WorldCommand>, mut try_times: i32) -> anyhow::Result<Self>{
         let handler = loop {
             if let Ok(conn) = channel::connect(addr.clone()){
                 break conn;
@@ -28,23 +59,27 @@ impl ExploreChannel{
             warn!("fai to connect to explore channel {}, try after 3 seconds",addr);
             std::thread::sleep(std::time::Duration::from_millis(3000));
         };
-        info!("explore_channel connect succes,session id {}",handler.id());
+        info!("explore_channel {} connect succes,session id {}",addr,handler.id());
         let (tx,rx) = crossbeam::channel::bounded(1);
         Ok(Self{
             handler, 
             heart_timer: IntervalTimer::new(12*1000),
             state: ChannelState::Connected, 
+            addr,
+            load: Default::default(),
+            load_updated: false,
             reconnect_handler: rx,
             reconnect_callback: tx, 
             msg_handler: msg_handler,
         })
     }
     pub fn reconnect_async(&mut self) -> anyhow::Result<()>{
-        let addr = format!("{}:{}", shared::libconfig::config::get_str("explore_channel_ip").unwrap(),
-        shared::libconfig::config::get::<i32>("explore_channel_port").expect("config explore_channel_port expected"));
+        let addr = self.addr.clone();
         let cb = self.reconnect_callback.clone();
+        super::super::metrics::inc_channel_reconnect();
         shared::db::send_query(Box::new(async move {
             while let Err(e) =  Self::reconnect(addr.clone(),cb.clone()).await{
+                super::super::metrics::inc_channel_reconnect_failure();
                 info!("explore_channel {} reconnect fail!{:?}, try again after 3sec",addr,e);
                 tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
                 info!("explore_channel reconnect fail!, try again");
@@ -63,8 +98,34 @@ impl ExploreChannel{
             self.handler = handler;
             self.state =  ChannelState::Connected;
             self.hand_shake().map_err(|e| logthrow!(e,"channel reconnect hand_shake fail",())).ok();
+            self.identify();
+            self.request_load();
         }
     }
+    ///告知探索服本平台服id
+    pub(crate) fn identify(&self){
+        let msg = match super::explore_manager::plat_identity() {
+            Ok(msg) => msg,
+            Err(e) => return error!("fail to build plat identity {:?}", e),
+        };
+        self.send_msg(msg).map_err(|_| error!("fail to identify to explore server {}", self.addr)).ok();
+    }
+    ///请求探索服上报负载
+    pub(crate) fn request_load(&self){
+        self.send_msg(shared::SessionTransport::new(
+            shared::proto::proto_code::DEFAULT_MAIN_CODE,
+            crate::msg_id::ES_EXPLORE_LOAD_REQ, 0, Box::new(protobuf::well_known_types::Empty::new())))
+        .map_err(|_| error!("fail to request load of explore server {}", self.addr)).ok();
+    }
+    #[inline]
+    pub(crate) fn load(&self) -> &ExploreLoad{
+        &self.load
+    }
+    ///取出上次读取后新上报的负载
+    pub(crate) fn take_load(&mut self) -> Option<ExploreLoad>{
+        if_else!(std::mem::take(&mut self.load_updated), Some(self.load.clone()), None)
+    }
+
     #[inline]
     fn reconnecting(&self) -> bool{
         self.state == ChannelState::Reconnecting
@@ -93,6 +154,9 @@ impl ServiceChannel<()> for ExploreChannel{
             handler, 
             heart_timer: IntervalTimer::new(12*1000),
             state, 
+            addr: String::new(),
+            load: Default::default(),
+            load_updated: false,
             reconnect_handler: rx,
             reconnect_callback: tx, 
             msg_handler: tx1,
@@ -110,11 +174,24 @@ impl ServiceChannel<()> for ExploreChannel{
         if self.heart_timer.passed(){
             self.heart_timer.reset();
             self.heartbeat().ok();
+            self.request_load();
         }
     }
 
     fn on_packet(&mut self, packet: shared::proto::PackBuffer)-> anyhow::Result<()> {
-        self.msg_handler.send(WorldCommand::ExploreMsg(packet))?;
+        if packet.header().sub_code() as u16 == crate::msg_id::ES_EXPLORE_LOAD_SYNC {
+            let pack = packet.unpack::<protobuf::well_known_types::StringValue>()
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+            self.load = serde_json::from_str(pack.get_value())?;
+            self.load_updated = true;
+            return Ok(());
+        }
+        //收到负载上报前不知道探索服id,丢弃消息,避免按id 0 路由
+        if self.load.server_id == 0 {
+            warn!("drop explore channel {} message {} before load reported", self.addr, packet.header().sub_code());
+            return Ok(());
+        }
+        self.msg_handler.send(WorldCommand::ExploreMsg(self.load.server_id, packet))?;
         Ok(())
     }
     #[inline]
@@ -1,29 +1,247 @@
+//! 探索服通道管理
+//!
+//! 探索服通过平台服 channel_port 连入并注册,也兼容按配置主动连接探索服
+//! 新探索分配到负载最低的探索服,玩家进行中的探索固定在原探索服
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
 use crossbeam::channel::Sender;
-use shared::{SessionTransport, server::channel::{ChannelState, ServiceChannel}};
-use once_cell::sync::OnceCell;
+use shared::{SessionTransport, proto::PackBuffer, server::channel::{ChannelState, ServiceChannel}};
+use once_cell::sync::{Lazy, OnceCell};
 use crate::server::world::WorldCommand;
 use shared::boxed::MutexArc;
-use super::{explore_channel::ExploreChannel};
-static EXPLORE_CLIENT: OnceCell<MutexArc<ExploreChannel>> = OnceCell::new();
+use super::explore_channel::{ExploreChannel, ExploreLoad};
+///注册的探索服超过该时间没有心跳视为离线
+const EXPLORE_SERVER_TIMEOUT: i64 = 36 * 1000;
+///按配置主动连接的探索服
+static EXPLORE_CLIENTS: OnceCell<Vec<MutexArc<ExploreChannel>>> = OnceCell::new();
+///探索服消息转发到world
+static MSG_HANDLER: OnceCell<Sender<WorldCommand>> = OnceCell::new();
+///探索服id -> 探索服
+///
+/// 同时需要两个锁时先锁 PLAYER_SERVERS 再锁 EXPLORE_SERVERS,持有 EXPLORE_SERVERS 时不能再锁 PLAYER_SERVERS
+static EXPLORE_SERVERS: Lazy<Mutex<BTreeMap<u32, ExploreServer>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+///玩家id -> 探索所在探索服id,创建请求发送成功后绑定,探索服返回创建成功时重新绑定(平台服重启后由此恢复)
+///
+/// 探索服下线时解除绑定,锁顺序见 EXPLORE_SERVERS
+static PLAYER_SERVERS: Lazy<Mutex<HashMap<u64, u32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+///探索服消息发送方式
+enum ExploreRoute{
+    ///平台服主动连接(EXPLORE_CLIENTS序号)
+    Dialed(usize),
+    ///探索服连入注册(通道会话id,发送队列)
+    Registered(usize, Sender<SessionTransport<()>>),
+}
+struct ExploreServer{
+    route: ExploreRoute,
+    load: ExploreLoad,
+    ///最后一次心跳时间
+    active_time: i64,
+}
+///平台服标识,握手后发给探索服,探索服据此路由消息
+#[derive(Debug, serde::Serialize)]
+struct PlatIdentity{
+    server_id: u32,
+}
+///本平台服id
+#[inline]
+pub fn plat_server_id() -> u32{
+    shared::libconfig::config::get("server_id").unwrap_or(1)
+}
+///本平台服标识消息
+pub(crate) fn plat_identity() -> anyhow::Result<SessionTransport<()>>{
+    let mut msg = protobuf::well_known_types::StringValue::new();
+    msg.set_value(serde_json::to_string(&PlatIdentity{ server_id: plat_server_id() })?);
+    Ok(SessionTransport::new(
+        shared::proto::proto_code::DEFAULT_MAIN_CODE,
+        crate::msg_id::ES_PLAT_SERVER_SYNC, 0, Box::new(msg)))
+}
+///按配置主动连接的探索服地址,兼容 explore_servers 及 explore_channel_ip/explore_channel_port
+fn server_addrs() -> Vec<String>{
+    match shared::libconfig::config::get_str("explore_servers") {
+        Some(servers) => servers.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+        None => match (shared::libconfig::config::get_str("explore_channel_ip"), shared::libconfig::config::get::<i32>("explore_channel_port")) {
+            (Some(ip), Some(port)) => vec![format!("{}:{}", ip, port)],
+            _ => Vec::new(),
+        },
+    }
+}
 ///启动探索服务通讯通道
 pub fn start_up(msg_handler: Sender<WorldCommand>) -> anyhow::Result<()>{
-    let channnel = ExploreChannel::start_up(msg_handler,10)?;
-    channnel.hand_shake()?;
-    EXPLORE_CLIENT.set(MutexArc::new(channnel)).map_err(|_| std::io::Error::from(std::io::ErrorKind::ConnectionAborted))?;
+    let mut clients = Vec::new();
+    for addr in server_addrs() {
+        let channnel = ExploreChannel::start_up(addr, msg_handler.clone(), 10)?;
+        channnel.hand_shake()?;
+        channnel.identify();
+        channnel.request_load();
+        clients.push(MutexArc::new(channnel));
+    }
+    info!("{} explore servers configured, waiting for explore servers to register", clients.len());
+    EXPLORE_CLIENTS.set(clients).map_err(|_| std::io::Error::from(std::io::ErrorKind::ConnectionAborted))?;
+    MSG_HANDLER.set(msg_handler).map_err(|_| std::io::Error::from(std::io::ErrorKind::ConnectionAborted))?;
     Ok(())
 }
 pub fn update(diff: i64){
-    let mut explore_channel = EXPLORE_CLIENT.get().unwrap().get_mut(None).unwrap();
-
-    explore_channel.update(diff as i64);
-    if explore_channel.state() == ChannelState::Disconnected{
-        //reconnect
-        explore_channel.reconnect_async().ok();
-        //error!("explore_channel disconnected!, reconnect now");
-    }
-}
-//向平台服发送消息
-pub fn send_msg(msg: SessionTransport<()>) -> anyhow::Result<()>{
-    EXPLORE_CLIENT.get().expect("探索通道未开启").get().send_msg(msg).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+    let now = shared::get_current_ms();
+    for (index, client) in EXPLORE_CLIENTS.get().unwrap().iter().enumerate() {
+        let (state, server_id, load) = {
+            let mut explore_channel = client.get_mut(None).unwrap();
+            explore_channel.update(diff as i64);
+            if explore_channel.state() == ChannelState::Disconnected{
+                //reconnect
+                explore_channel.reconnect_async().ok();
+            }
+            (explore_channel.state(), explore_channel.load().server_id, explore_channel.take_load())
+        };
+        //收到负载上报后才能分配探索
+        if server_id == 0 {
+            continue;
+        }
+        let mut servers = EXPLORE_SERVERS.lock().unwrap();
+        if state != ChannelState::Connected {
+            if matches!(servers.get(&server_id).map(|s| &s.route), Some(ExploreRoute::Dialed(_))) {
+                servers.remove(&server_id);
+                drop(servers);
+                unbind_servers(&[server_id]);
+            }
+            continue;
+        }
+        match (servers.get_mut(&server_id), load) {
+            (Some(server), None) => server.active_time = now,
+            (_, Some(load)) => {
+                servers.insert(server_id, ExploreServer{ route: ExploreRoute::Dialed(index), load, active_time: now });
+            },
+            (None, None) => (),
+        }
+    }
+    let mut removed = Vec::new();
+    EXPLORE_SERVERS.lock().unwrap().retain(|server_id, server| {
+        let alive = now - server.active_time < EXPLORE_SERVER_TIMEOUT;
+        if !alive {
+            warn!("explore server {} heartbeat lapsed, removed", server_id);
+            removed.push(*server_id);
+        }
+        alive
+    });
+    unbind_servers(&removed);
+}
+///探索服连入注册
+pub fn register(session_id: usize, load: ExploreLoad, sender: Sender<SessionTransport<()>>){
+    info!("explore server {} registered from channel {}, address {}:{}, capacity {}", load.server_id, session_id, load.server_ip, load.server_port, load.capacity);
+    EXPLORE_SERVERS.lock().unwrap().insert(load.server_id, ExploreServer{
+        route: ExploreRoute::Registered(session_id, sender),
+        load,
+        active_time: shared::get_current_ms(),
+    });
+}
+///注册的探索服上报负载
+pub fn on_load(session_id: usize, load: ExploreLoad){
+    let mut servers = EXPLORE_SERVERS.lock().unwrap();
+    match servers.get_mut(&load.server_id) {
+        Some(server) if matches!(server.route, ExploreRoute::Registered(id, _) if id == session_id) => {
+            server.load = load;
+            server.active_time = shared::get_current_ms();
+        },
+        _ => warn!("load of unregistered explore server {} from channel {}", load.server_id, session_id),
+    }
+}
+///注册的探索服断开
+pub fn unregister(session_id: usize){
+    let mut removed = Vec::new();
+    EXPLORE_SERVERS.lock().unwrap().retain(|server_id, server| {
+        let keep = !matches!(server.route, ExploreRoute::Registered(id, _) if id == session_id);
+        if !keep {
+            info!("explore server {} unregistered", server_id);
+            removed.push(*server_id);
+        }
+        keep
+    });
+    unbind_servers(&removed);
+}
+///探索服下线,解除玩家与其的绑定,调用时不能持有 EXPLORE_SERVERS 锁
+fn unbind_servers(server_ids: &[u32]){
+    if server_ids.is_empty() {
+        return;
+    }
+    PLAYER_SERVERS.lock().unwrap().retain(|player_id, server_id| {
+        let keep = !server_ids.contains(server_id);
+        if !keep {
+            info!("player {} unbound from offline explore server {}", player_id, server_id);
+        }
+        keep
+    });
+}
+///注册的探索服消息,转发到world处理
+pub fn on_explore_msg(server_id: u32, packet: PackBuffer) -> anyhow::Result<()>{
+    MSG_HANDLER.get().expect("探索通道未开启").send(WorldCommand::ExploreMsg(server_id, packet))?;
+    Ok(())
+}
+///负载最低的可用探索服
+pub fn least_loaded() -> Option<u32>{
+    EXPLORE_SERVERS.lock().unwrap().iter()
+    .filter(|(_, server)| server.load.available())
+    .min_by_key(|(_, server)| server.load.bearing)
+    .map(|(server_id, _)| *server_id)
+}
+///玩家进行中探索所在的探索服
+pub fn player_server(player_id: u64) -> Option<u32>{
+    PLAYER_SERVERS.lock().unwrap().get(&player_id).copied()
+}
+///选择玩家的探索服,已有探索时使用原探索服,否则选择负载最低的可用探索服
+///
+/// 不绑定玩家,请求发送成功后由调用方 bind_player
+pub fn select_server(player_id: u64) -> Option<u32>{
+    if let Some(server) = player_server(player_id) {
+        return Some(server);
+    }
+    let mut servers = EXPLORE_SERVERS.lock().unwrap();
+    let (server_id, server) = servers.iter_mut()
+    .filter(|(_, server)| server.load.available())
+    .min_by_key(|(_, server)| server.load.bearing)?;
+    //下次上报前预估负载
+    server.load.bearing += 1;
+    Some(*server_id)
+}
+///绑定玩家探索所在的探索服
+pub fn bind_player(player_id: u64, server_id: u32){
+    PLAYER_SERVERS.lock().unwrap().insert(player_id, server_id);
+}
+///玩家探索结束或创建失败,解除与探索服的绑定
+pub fn unbind_player(player_id: u64){
+    PLAYER_SERVERS.lock().unwrap().remove(&player_id);
+}
+///探索服的玩家连接地址
+pub fn server_address(server_id: u32) -> Option<(String, i32)>{
+    let servers = EXPLORE_SERVERS.lock().unwrap();
+    let load = &servers.get(&server_id)?.load;
+    if load.server_ip.is_empty() {
+        return None;
+    }
+    Some((load.server_ip.clone(), load.server_port))
+}
+//向探索服发送消息
+pub fn send_msg(server_id: u32, msg: SessionTransport<()>) -> anyhow::Result<()>{
+    let servers = EXPLORE_SERVERS.lock().unwrap();
+    match servers.get(&server_id).map(|server| &server.route) {
+        Some(ExploreRoute::Dialed(index)) => {
+            EXPLORE_CLIENTS.get().expect("探索通道未开启")[*index]
+            .get().send_msg(msg).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        },
+        Some(ExploreRoute::Registered(_, sender)) => {
+            sender.send(msg).map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        },
+        None => return shared::error::any_err(std::io::ErrorKind::NotFound),
+    }
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+#[test]
+fn test_unbind_offline_server(){
+    bind_player(1, 10);
+    bind_player(2, 11);
+    unbind_servers(&[10]);
+    assert_eq!(player_server(1), None);
+    assert_eq!(player_server(2), Some(11));
+    //探索服下线后重新选择探索服
+    assert_eq!(select_server(1), None);
+    unbind_player(2);
+}
@@ -0,0 +1,128 @@
+//! 运行指标
+//!
+//! 以prometheus文本格式输出,通过配置项 metrics_addr 启动一个简单的http监听
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::{BufRead, BufReader, Write as IoWrite};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use once_cell::sync::Lazy;
+///指标请求读写超时,避免慢连接阻塞监听线程
+const METRICS_IO_TIMEOUT: Duration = Duration::from_secs(3);
+///耗时统计
+#[derive(Debug, Default, Clone, Copy)]
+struct Timing{
+    count: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+///数据库操作耗时(操作名)
+static DB_TIMINGS: Lazy<Mutex<BTreeMap<&'static str, Timing>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+///世界循环次数
+static TICK_COUNT: AtomicU64 = AtomicU64::new(0);
+///世界循环总耗时(毫秒)
+static TICK_SUM_MS: AtomicU64 = AtomicU64::new(0);
+///最近一次世界循环耗时(毫秒)
+static TICK_LAST_MS: AtomicU64 = AtomicU64::new(0);
+///在线会话数
+static ONLINE_SESSIONS: AtomicU64 = AtomicU64::new(0);
+///登录中会话数
+static QUEUED_SESSIONS: AtomicU64 = AtomicU64::new(0);
+///探索服通道重连次数
+static CHANNEL_RECONNECTS: AtomicU64 = AtomicU64::new(0);
+///探索服通道重连失败次数
+static CHANNEL_RECONNECT_FAILURES: AtomicU64 = AtomicU64::new(0);
+///记录世界循环耗时
+pub fn observe_tick(cost_ms: u64){
+    TICK_COUNT.fetch_add(1, Ordering::Relaxed);
+    TICK_SUM_MS.fetch_add(cost_ms, Ordering::Relaxed);
+    TICK_LAST_MS.store(cost_ms, Ordering::Relaxed);
+}
+///记录会话数量
+pub fn set_sessions(online: usize, queued: usize){
+    ONLINE_SESSIONS.store(online as u64, Ordering::Relaxed);
+    QUEUED_SESSIONS.store(queued as u64, Ordering::Relaxed);
+}
+///记录数据库操作耗时
+pub fn observe_db(query: &'static str, elapsed: Duration){
+    let us = elapsed.as_micros() as u64;
+    let mut timings = DB_TIMINGS.lock().unwrap();
+    let timing = timings.entry(query).or_default();
+    timing.count += 1;
+    timing.sum_us += us;
+    timing.max_us = timing.max_us.max(us);
+}
+#[inline]
+pub fn inc_channel_reconnect(){
+    CHANNEL_RECONNECTS.fetch_add(1, Ordering::Relaxed);
+}
+#[inline]
+pub fn inc_channel_reconnect_failure(){
+    CHANNEL_RECONNECT_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display){
+    writeln!(out, "# HELP {} {}", name, help).ok();
+    writeln!(out, "# TYPE {} {}", name, kind).ok();
+    writeln!(out, "{} {}", name, value).ok();
+}
+///输出全部指标
+pub fn render() -> String{
+    let mut out = String::new();
+    writeln!(out, "# HELP plat_world_tick_duration_seconds World loop update time").ok();
+    writeln!(out, "# TYPE plat_world_tick_duration_seconds summary").ok();
+    writeln!(out, "plat_world_tick_duration_seconds_sum {}", TICK_SUM_MS.load(Ordering::Relaxed) as f64 / 1000.0).ok();
+    writeln!(out, "plat_world_tick_duration_seconds_count {}", TICK_COUNT.load(Ordering::Relaxed)).ok();
+    write_metric(&mut out, "plat_world_tick_last_seconds", "gauge", "Last world loop update time", TICK_LAST_MS.load(Ordering::Relaxed) as f64 / 1000.0);
+    write_metric(&mut out, "plat_online_sessions", "gauge", "Sessions in the world session map", ONLINE_SESSIONS.load(Ordering::Relaxed));
+    write_metric(&mut out, "plat_queued_sessions", "gauge", "Sessions still logging in", QUEUED_SESSIONS.load(Ordering::Relaxed));
+    write_metric(&mut out, "plat_explore_channel_reconnects_total", "counter", "Explore channel reconnects", CHANNEL_RECONNECTS.load(Ordering::Relaxed));
+    write_metric(&mut out, "plat_explore_channel_reconnect_failures_total", "counter", "Failed explore channel reconnect attempts", CHANNEL_RECONNECT_FAILURES.load(Ordering::Relaxed));
+    let timings = DB_TIMINGS.lock().unwrap();
+    writeln!(out, "# HELP plat_db_query_duration_seconds Database operation latency").ok();
+    writeln!(out, "# TYPE plat_db_query_duration_seconds summary").ok();
+    for (query, t) in timings.iter() {
+        writeln!(out, "plat_db_query_duration_seconds_sum{{query=\"{}\"}} {}", query, t.sum_us as f64 / 1_000_000.0).ok();
+        writeln!(out, "plat_db_query_duration_seconds_count{{query=\"{}\"}} {}", query, t.count).ok();
+    }
+    writeln!(out, "# TYPE plat_db_query_duration_max_seconds gauge").ok();
+    for (query, t) in timings.iter() {
+        writeln!(out, "plat_db_query_duration_max_seconds{{query=\"{}\"}} {}", query, t.max_us as f64 / 1_000_000.0).ok();
+    }
+    out
+}
+fn handle_connection(mut stream: TcpStream) -> std::io::Result<()>{
+    stream.set_read_timeout(Some(METRICS_IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(METRICS_IO_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let (status, body) = if request_line.starts_with("GET /metrics") {
+        ("200 OK", render())
+    }
+    else{
+        ("404 Not Found", String::new())
+    };
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)?;
+    stream.flush()
+}
+///启动指标监听,未配置 metrics_addr 时不启动
+pub fn start_up() -> anyhow::Result<()>{
+    let addr = match shared::libconfig::config::get_str("metrics_addr") {
+        Some(addr) => addr,
+        None => return Ok(()),
+    };
+    let listener = TcpListener::bind(&addr).map_err(|e| logthrow!(e,e))?;
+    info!("metrics listening on {}", addr);
+    std::thread::Builder::new().name("metrics".to_string()).spawn(move ||{
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    handle_connection(stream).map_err(|e| warn!("metrics request fail {:?}", e)).ok();
+                },
+                Err(e) => warn!("metrics accept fail {:?}", e),
+            }
+        }
+    })?;
+    Ok(())
+}
@@ -2,4 +2,5 @@ pub mod start;
 mod world;
 mod world_session;
 mod world_session_handler;
+pub(crate) mod metrics;
 pub mod channel;
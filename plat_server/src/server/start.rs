@@ -16,6 +16,7 @@ fn world_loop() -> anyhow::Result<()>{
     //channel 服务
     super::channel::channel_service::start_up()?;
     super::channel::explore_manager::start_up(world.world_cmd_handler())?;
+    super::metrics::start_up()?;
     let mut last_time = get_current_ms();
     let mut _current_time = last_time;
     world.start().expect("fail to start plat server!");
@@ -42,6 +43,7 @@ fn world_loop() -> anyhow::Result<()>{
             LOOP_TIMER.store(0, Ordering::Release);        
         }
         let update_cost = (get_current_ms() - _current_time).max(0) as u64;
+        super::metrics::observe_tick(update_cost);
         if update_cost < WORLD_SLEEP_CONST{
             std::thread::sleep(std::time::Duration::from_millis(WORLD_SLEEP_CONST-update_cost));
         }
@@ -70,8 +72,7 @@ pub fn start() -> anyhow::Result<()>{
         }
     }));
     shared::libconfig::common::load_config("configs/json/Common.json")?;
-    let _: String = shared::libconfig::config::get_str("explore_server_ip").expect("fail to load explore_server_ip from config");
-    let _: i32 = shared::libconfig::config::get("explore_server_port").expect("fail to load explore_server_port from config");
+    crate::config::explore_reward::load_config("configs/json/ExploreReward.json")?;
     
     let _: String = shared::libconfig::config::get_str("battle_server_ip").expect("fail to load battle_server_ip from config");
     let _: i32 = shared::libconfig::config::get("battle_server_port").expect("fail to load battle_server_port from config");
@@ -79,9 +80,14 @@ pub fn start() -> anyhow::Result<()>{
     let ip: String = shared::libconfig::config::get_str("bind_ip").expect("fail to load ip from config");
     let port: i32 = shared::libconfig::config::get("bind_port").expect("fail to load port from config");
     shared::server::worker::init(0)?;
+    crate::player::storage::init()?;
+    //只执行数据库迁移
+    if env::args().any(|arg| arg == "--migrate"){
+        info!("plat database migrated");
+        return Ok(());
+    }
     shared::server::worker::run::<()>(&(ip+":"+&port.to_string()),true);
     //shared::server::worker::run::<()>(&(ip+":"+&port.to_string()),true);   
-    shared::db::start_pools(vec![shared::db::DbPoolInfo{db_path: shared::libconfig::config::get_str("player_db").expect("fail to load db_path"), db_name: "bg_db_server".to_string(),max_conn: 20}]);
     world_loop()?;
     Ok(())
 }
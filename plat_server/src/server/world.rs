@@ -32,8 +32,10 @@ impl WorldBuilder{
 }
 ///world command eunm
 pub enum WorldCommand{
-    ///探索服消息
-    ExploreMsg(PackBuffer),
+    ///探索服消息(探索服id,消息)
+    ExploreMsg(u32, PackBuffer),
+    ///探索结算已保存
+    ExploreSettled(crate::player::ExploreSettlement),
 }
 ///daily task timer index
 const WORLD_TIMER_DAILY: usize = 0;
@@ -173,30 +175,39 @@ impl World{
         }
         //session update
         self.session_map.iter_mut().for_each(|(_,session)| session.update(diff));
+        super::metrics::set_sessions(self.session_map.len(), self.queued_sessions.len());
     }
     fn handle_cmd(&mut self){
         while let  Some(cmd) = self.recv_cmd() {
             match cmd{
-                WorldCommand::ExploreMsg(packet) => {
-                    if let Err(e) = self.handle_explore_channel_msg(packet){
+                WorldCommand::ExploreMsg(server, packet) => {
+                    if let Err(e) = self.handle_explore_channel_msg(server, packet){
                         error!("recv error packet from explore_channel {:?}",e);
                     }
                 },
+                WorldCommand::ExploreSettled(settlement) => self.on_explore_settled(settlement),
                 //_ => (),
             }
         }
     }
     ///处理探索服消息
-    fn handle_explore_channel_msg(&mut self, packet: PackBuffer) -> anyhow::Result<()> {
+    fn handle_explore_channel_msg(&mut self, server: u32, packet: PackBuffer) -> anyhow::Result<()> {
         let header = packet.header();
         match header.sub_code() as u16{
             shared::proto::proto_code::msg_id_es_ps::CREATE_EXPLORE_RESP => {
                 let pack = packet.unpack::<shared::proto::Es2PsMsgExploreResp>()
                 .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
                 let player_id = pack.get_player_id();
+                //以探索服返回为准绑定,平台服重启后玩家重新进入时恢复绑定
+                if pack.get_result() == shared::proto::ExploreCreateResult::SUCCESS {
+                    super::channel::explore_manager::bind_player(player_id, server);
+                }
+                else {
+                    super::channel::explore_manager::unbind_player(player_id);
+                }
                 if let Some(session) = self.player_sessions.get(&player_id){
                     if let Some(session) = self.session_map.get_mut(session){
-                        session.on_explore_create_resp(pack)?;
+                        session.on_explore_create_resp(server, pack)?;
                     }
                     else{
                         warn!("player disconnected before explore started {}",player_id);
@@ -206,10 +217,36 @@ impl World{
                     warn!("player disconnected before explore started {}",player_id);
                 }
             }
+            crate::msg_id::ES_EXPLORE_SETTLE_SYNC => {
+                let pack = packet.unpack::<protobuf::well_known_types::StringValue>()
+                .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+                let sync = serde_json::from_str::<super::channel::explore_channel::ExploreSettleSync>(pack.get_value()).map_err(|e| logthrow!(e, e))?;
+                let (player_id, result) = (sync.player_id, sync.result);
+                super::channel::explore_manager::unbind_player(player_id);
+                let rewards = crate::config::explore_reward::get_rewards(result);
+                info!("player {} explore {} end with result {}, rewards {:?}", player_id, sync.explore_uuid, result, rewards);
+                let sender = self.command_sender.clone();
+                crate::player::DbHandler::settle_explore(player_id, sync.explore_uuid, result, rewards, move |settlement|{
+                    sender.send(WorldCommand::ExploreSettled(settlement)).map_err(|_| error!("fail to send explore settlement of player {} to world", player_id)).ok();
+                })?;
+            }
             opcode => {
                 error!("unexpected opcode from explore channel {}",opcode);                
             }
         }
         Ok(())
     }
+    ///探索结算保存后通知在线玩家,离线玩家在下次登录时通知
+    fn on_explore_settled(&mut self, settlement: crate::player::ExploreSettlement){
+        let player_id = settlement.player_id;
+        match self.player_sessions.get(&player_id).and_then(|session| self.session_map.get_mut(session)){
+            Some(session) => {
+                session.on_explore_settled(settlement)
+                .map_err(|e| error!("fail to notify explore settlement to player {}, {:?}", player_id, e)).ok();
+            },
+            None => {
+                info!("player {} offline, explore settlement {} queued", player_id, settlement.id);
+            }
+        }
+    }
 }
\ No newline at end of file
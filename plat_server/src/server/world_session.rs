@@ -266,12 +266,19 @@ impl WorldSession{
                         info!("Player {} load success, but response fail", self.player.get_name());
                         self.state = WorldSessionState::KickOff;
                     }
+                    else if self.state == WorldSessionState::Normal{
+                        //下发离线期间的探索结算
+                        self.player.flush_explore_results().map_err(|e| error!("player {} fail to flush explore results {:?}", self.player_id(), e)).ok();
+                    }
                 }
             },
             _ => (),
         }
     }
-    pub fn on_explore_create_resp(&mut self, resp: shared::proto::Es2PsMsgExploreResp)  -> anyhow::Result<()>{
-        self.player.on_explore_create_resp(resp)
+    pub fn on_explore_create_resp(&mut self, server: u32, resp: shared::proto::Es2PsMsgExploreResp)  -> anyhow::Result<()>{
+        self.player.on_explore_create_resp(server, resp)
     } 
+    pub fn on_explore_settled(&mut self, settlement: ExploreSettlement) -> anyhow::Result<()>{
+        self.player.on_explore_settled(settlement)
+    }
 }
\ No newline at end of file